@@ -0,0 +1,85 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// How an identifier such as an entry type or field name is cased in the
+/// output.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Case {
+    /// Lowercase, e.g. `article`, `string`, `author`.
+    Lower,
+    /// Uppercase, e.g. `ARTICLE`, `STRING`, `AUTHOR`.
+    Upper,
+    /// First letter uppercase, the rest lowercase, e.g. `Article`, `String`,
+    /// `Author`.
+    Capitalized,
+    /// As written in the source.
+    Preserve,
+}
+
+impl Case {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Self::Lower => text.to_lowercase(),
+            Self::Upper => text.to_uppercase(),
+            Self::Capitalized => {
+                let mut chars = text.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first
+                        .to_uppercase()
+                        .chain(chars.flat_map(char::to_lowercase))
+                        .collect()
+                })
+            }
+            Self::Preserve => text.to_string(),
+        }
+    }
+}
+
+/// How cite keys are cased in the output.
+///
+/// Cite keys are case-sensitive in biber and in `\cite{}` commands, so
+/// lowercasing a key breaks documents that cite it as written.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyCase {
+    /// Lowercase every key.
+    #[default]
+    Lowercase,
+    /// Keep keys as written.
+    Preserve,
+    /// Keep keys as written and fail on keys that differ only in case.
+    ///
+    /// See [`crate::models::Entries::check_key_case`].
+    Strict,
+}
+
+impl KeyCase {
+    pub fn apply(self, key: &str) -> String {
+        match self {
+            Self::Lowercase => key.to_lowercase(),
+            Self::Preserve | Self::Strict => key.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case() {
+        assert_eq!(Case::Lower.apply("InProceedings"), "inproceedings");
+        assert_eq!(Case::Upper.apply("InProceedings"), "INPROCEEDINGS");
+        assert_eq!(Case::Capitalized.apply("InProceedings"), "Inproceedings");
+        assert_eq!(Case::Preserve.apply("InProceedings"), "InProceedings");
+        assert_eq!(Case::Capitalized.apply(""), "");
+    }
+
+    #[test]
+    fn test_key_case() {
+        assert_eq!(KeyCase::Lowercase.apply("Smith2020"), "smith2020");
+        assert_eq!(KeyCase::Preserve.apply("Smith2020"), "Smith2020");
+        assert_eq!(KeyCase::Strict.apply("Smith2020"), "Smith2020");
+    }
+}
@@ -0,0 +1,365 @@
+use crate::case::{Case, KeyCase};
+use crate::format::{DelimiterStyle, FormatterBuilder, Indent};
+use crate::models::{MonthStyle, NameStyle};
+use crate::order::{EntrySort, TagFallback, TagOrder};
+use crate::{Error, Result};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the project configuration file, discovered by walking up from
+/// each input file's directory.
+pub const CONFIG_FILE: &str = ".bibtex-format.toml";
+
+/// Formatter settings read from a `.bibtex-format.toml` file.
+///
+/// Every setting is optional so that a file only needs to list what it changes
+/// and so that command line flags can be layered on top with [`Config::or`].
+/// Keys are the kebab-case names of the settings, e.g.:
+///
+/// ```toml
+/// sort-entries = false
+/// remove-comments = true
+/// crossref = "verify"
+/// tag-order = ["author", "title", "year"]
+///
+/// [tag-order-by-type]
+/// article = ["author", "title", "journal"]
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub format_title: Option<bool>,
+    pub remove_comments: Option<bool>,
+    pub remove_empty_tags: Option<bool>,
+    pub sort_entries: Option<bool>,
+    pub sort_entries_by: Option<EntrySort>,
+    pub natural_key_sort: Option<bool>,
+    pub unicode_key_sort: Option<bool>,
+    pub key_case: Option<KeyCase>,
+    pub entry_type_case: Option<Case>,
+    pub keyword_case: Option<Case>,
+    pub field_name_case: Option<Case>,
+    pub indent: Option<Indent>,
+    pub align_values: Option<bool>,
+    pub delimiters: Option<DelimiterStyle>,
+    pub max_width: Option<usize>,
+    pub trailing_comma: Option<bool>,
+    pub normalize_pages: Option<bool>,
+    pub normalize_names: Option<NameStyle>,
+    pub sort_tags: Option<bool>,
+    pub tag_order: Option<Vec<String>>,
+    pub tag_order_by_type: Option<BTreeMap<String, Vec<String>>>,
+    pub tag_order_fallback: Option<TagFallback>,
+    pub remove_duplicates: Option<bool>,
+    pub expand_strings: Option<bool>,
+    pub factor_strings: Option<usize>,
+    pub crossref: Option<CrossrefMode>,
+    pub normalize_months: Option<MonthStyle>,
+    pub normalize_links: Option<bool>,
+    pub move_doi_urls: Option<bool>,
+}
+
+/// How crossref/xdata inheritance is handled (see
+/// [`crate::models::Entries::resolve_crossrefs`]).
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrossrefMode {
+    /// Copy inherited fields into each entry and drop crossref/xdata.
+    Inline,
+    /// Report missing parents, cycles, and parents that precede a child.
+    Verify,
+}
+
+impl Config {
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)
+            .map_err(|error| Error::InvalidConfig(error.to_string().trim_end().to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::from_toml(&text).map_err(|error| match error {
+            Error::InvalidConfig(message) => {
+                Error::InvalidConfig(format!("{}: {message}", path.display()))
+            }
+            error => error,
+        })
+    }
+
+    /// Reject settings that cannot be used together. Check again after
+    /// layering settings with [`Config::or`], since each layer may be valid on
+    /// its own.
+    pub fn validate(&self) -> Result<()> {
+        if self.expand_strings == Some(true) && self.factor_strings.is_some() {
+            return Err(Error::InvalidConfig(
+                "`expand-strings` and `factor-strings` cannot be used together".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Find the configuration file closest to `input` by checking its
+    /// directory and then each parent directory in turn.
+    pub fn discover(input: &Path) -> Option<PathBuf> {
+        let input = std::path::absolute(input).ok()?;
+        input
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Settings from `self`, falling back to `other` for any left unset.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            format_title: self.format_title.or(other.format_title),
+            remove_comments: self.remove_comments.or(other.remove_comments),
+            remove_empty_tags: self.remove_empty_tags.or(other.remove_empty_tags),
+            sort_entries: self.sort_entries.or(other.sort_entries),
+            sort_entries_by: self.sort_entries_by.or(other.sort_entries_by),
+            natural_key_sort: self.natural_key_sort.or(other.natural_key_sort),
+            unicode_key_sort: self.unicode_key_sort.or(other.unicode_key_sort),
+            key_case: self.key_case.or(other.key_case),
+            entry_type_case: self.entry_type_case.or(other.entry_type_case),
+            keyword_case: self.keyword_case.or(other.keyword_case),
+            field_name_case: self.field_name_case.or(other.field_name_case),
+            indent: self.indent.or(other.indent),
+            align_values: self.align_values.or(other.align_values),
+            delimiters: self.delimiters.or(other.delimiters),
+            max_width: self.max_width.or(other.max_width),
+            trailing_comma: self.trailing_comma.or(other.trailing_comma),
+            normalize_pages: self.normalize_pages.or(other.normalize_pages),
+            normalize_names: self.normalize_names.or(other.normalize_names),
+            sort_tags: self.sort_tags.or(other.sort_tags),
+            tag_order: self.tag_order.or(other.tag_order),
+            // Per-type lists merge, with `self` winning for types set in both.
+            tag_order_by_type: match (self.tag_order_by_type, other.tag_order_by_type) {
+                (Some(mine), Some(mut theirs)) => {
+                    theirs.extend(mine);
+                    Some(theirs)
+                }
+                (mine, theirs) => mine.or(theirs),
+            },
+            tag_order_fallback: self.tag_order_fallback.or(other.tag_order_fallback),
+            remove_duplicates: self.remove_duplicates.or(other.remove_duplicates),
+            expand_strings: self.expand_strings.or(other.expand_strings),
+            factor_strings: self.factor_strings.or(other.factor_strings),
+            crossref: self.crossref.or(other.crossref),
+            normalize_months: self.normalize_months.or(other.normalize_months),
+            normalize_links: self.normalize_links.or(other.normalize_links),
+            move_doi_urls: self.move_doi_urls.or(other.move_doi_urls),
+        }
+    }
+
+    /// Apply the formatter settings that are set onto `builder`. Settings that
+    /// transform entries before formatting (e.g. `expand-strings`) are left
+    /// for the caller.
+    pub fn apply(&self, mut builder: FormatterBuilder) -> FormatterBuilder {
+        if let Some(format_title) = self.format_title {
+            builder = builder.format_title(format_title);
+        }
+        if let Some(remove_comments) = self.remove_comments {
+            builder = builder.remove_comments(remove_comments);
+        }
+        if let Some(remove_empty_tags) = self.remove_empty_tags {
+            builder = builder.skip_empty_tags(remove_empty_tags);
+        }
+        if let Some(sort_entries) = self.sort_entries {
+            builder = builder.sort_entries(sort_entries);
+        }
+        if let Some(entry_sort) = self.sort_entries_by {
+            builder = builder.entry_sort(entry_sort);
+        }
+        if let Some(natural_key_sort) = self.natural_key_sort {
+            builder = builder.natural_key_sort(natural_key_sort);
+        }
+        if let Some(unicode_key_sort) = self.unicode_key_sort {
+            builder = builder.unicode_key_sort(unicode_key_sort);
+        }
+        if let Some(key_case) = self.key_case {
+            builder = builder.key_case(key_case);
+        }
+        if let Some(entry_type_case) = self.entry_type_case {
+            builder = builder.entry_type_case(entry_type_case);
+        }
+        if let Some(keyword_case) = self.keyword_case {
+            builder = builder.keyword_case(keyword_case);
+        }
+        if let Some(field_name_case) = self.field_name_case {
+            builder = builder.field_name_case(field_name_case);
+        }
+        if let Some(indent) = self.indent {
+            builder = builder.indent(indent);
+        }
+        if let Some(align_values) = self.align_values {
+            builder = builder.align_values(align_values);
+        }
+        if let Some(delimiters) = self.delimiters {
+            builder = builder.delimiters(delimiters);
+        }
+        if let Some(max_width) = self.max_width {
+            builder = builder.max_width(max_width);
+        }
+        if let Some(trailing_comma) = self.trailing_comma {
+            builder = builder.trailing_comma(trailing_comma);
+        }
+        if let Some(normalize_pages) = self.normalize_pages {
+            builder = builder.normalize_pages(normalize_pages);
+        }
+        if let Some(style) = self.normalize_names {
+            builder = builder.normalize_names(style);
+        }
+        if let Some(sort_tags) = self.sort_tags {
+            builder = builder.sort_tags(sort_tags);
+        }
+        if self.tag_order.is_some()
+            || self.tag_order_by_type.is_some()
+            || self.tag_order_fallback.is_some()
+        {
+            let mut order = self
+                .tag_order
+                .as_ref()
+                .map_or_else(TagOrder::default, TagOrder::new);
+            for (kind, fields) in self.tag_order_by_type.iter().flatten() {
+                order = order.with_type(kind, fields);
+            }
+            if let Some(fallback) = self.tag_order_fallback {
+                order = order.with_fallback(fallback);
+            }
+            builder = builder.tag_order(order);
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Formatter;
+
+    #[test]
+    fn test_from_toml() -> Result<()> {
+        let config = Config::from_toml("sort-entries = false\ncrossref = \"verify\"\n")?;
+        assert_eq!(
+            config,
+            Config {
+                sort_entries: Some(false),
+                crossref: Some(CrossrefMode::Verify),
+                ..Config::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_toml_rejects_unknown_keys() {
+        let error = Config::from_toml("sort-entry = false\n").unwrap_err();
+        assert!(
+            error.to_string().contains("unknown field `sort-entry`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_from_toml_rejects_expand_and_factor_strings() {
+        let error = Config::from_toml("expand-strings = true\nfactor-strings = 2\n").unwrap_err();
+        assert!(
+            error.to_string().contains("cannot be used together"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_validate_layered() {
+        let flags = Config {
+            factor_strings: Some(2),
+            ..Config::default()
+        };
+        let file = Config {
+            expand_strings: Some(true),
+            ..Config::default()
+        };
+        assert!(flags.validate().is_ok());
+        assert!(file.validate().is_ok());
+        assert!(flags.or(file).validate().is_err());
+    }
+
+    #[test]
+    fn test_from_toml_indent() -> Result<()> {
+        assert_eq!(
+            Config::from_toml("indent = 2\n")?.indent,
+            Some(Indent::Spaces(2))
+        );
+        assert_eq!(
+            Config::from_toml("indent = \"tab\"\n")?.indent,
+            Some(Indent::Tab)
+        );
+        assert!(Config::from_toml("indent = \"wide\"\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_or_prefers_self() {
+        let cli = Config {
+            sort_tags: Some(false),
+            ..Config::default()
+        };
+        let file = Config {
+            sort_tags: Some(true),
+            remove_comments: Some(true),
+            ..Config::default()
+        };
+        let merged = cli.or(file);
+        assert_eq!(merged.sort_tags, Some(false));
+        assert_eq!(merged.remove_comments, Some(true));
+    }
+
+    #[test]
+    fn test_apply() {
+        let config = Config {
+            sort_entries: Some(false),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.apply(Formatter::builder()).build(),
+            Formatter::builder().sort_entries(false).build()
+        );
+    }
+
+    #[test]
+    fn test_apply_tag_order() -> Result<()> {
+        let config = Config::from_toml(
+            "tag-order = [\"author\"]\ntag-order-fallback = \"original\"\n\
+             [tag-order-by-type]\narticle = [\"journal\"]\n",
+        )?;
+        let order = TagOrder::new(["author"])
+            .with_type("article", ["journal"])
+            .with_fallback(TagFallback::Original);
+        assert_eq!(
+            config.apply(Formatter::builder()).build(),
+            Formatter::builder().tag_order(order).build()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_walks_up() {
+        let root = std::env::temp_dir().join("bibtex-format-config-discover");
+        let nested = root.join("a").join("b");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join(CONFIG_FILE), "").unwrap();
+
+        let input = nested.join("refs.bib");
+        assert_eq!(Config::discover(&input), Some(root.join(CONFIG_FILE)));
+
+        fs::write(nested.join(CONFIG_FILE), "").unwrap();
+        assert_eq!(Config::discover(&input), Some(nested.join(CONFIG_FILE)));
+    }
+}
@@ -0,0 +1,442 @@
+//! A lossless concrete syntax tree for surgical edits to bibtex sources.
+//!
+//! The [`crate::models`] types normalize away whitespace, delimiters, casing,
+//! and field order, so formatting them rewrites the whole file. A [`Document`]
+//! instead keeps the source text untouched and indexes it with byte ranges for
+//! each entry, cite key, and field. Edits splice new text into exactly the
+//! range they affect, so writing a document back produces a diff containing
+//! only the requested change.
+//!
+//! The tree is built from the spans recorded by [`Parser`], so it accepts the
+//! same syntax. Anything that is not an entry (whitespace, implicit comments,
+//! and malformed entries) is kept as [`Item::Trivia`].
+
+use crate::models::{CommentKind, EntryType, Tag};
+use crate::parse::Parser;
+use crate::token::{Span, Tokenizer};
+use crate::{Error, Result};
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Item {
+    Entry(EntryNode),
+    Trivia(Range<usize>),
+}
+
+/// An entry in the source. `key` is set for reference entries only; `fields`
+/// holds the tags of reference entries and the definition of `@string` entries.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EntryNode {
+    pub range: Range<usize>,
+    pub kind: Range<usize>,
+    pub key: Option<Range<usize>>,
+    pub fields: Vec<FieldNode>,
+}
+
+/// A `name = value` field; `value` covers the value exactly as written,
+/// including its delimiters.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldNode {
+    pub range: Range<usize>,
+    pub name: Range<usize>,
+    pub value: Range<usize>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Document {
+    source: String,
+    items: Vec<Item>,
+}
+
+impl Document {
+    pub fn parse(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let items = build(&source);
+        Self { source, items }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Every item in source order. The items' ranges tile the source exactly.
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn text(&self, range: &Range<usize>) -> &str {
+        &self.source[range.clone()]
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &EntryNode> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Entry(entry) => Some(entry),
+            Item::Trivia(_) => None,
+        })
+    }
+
+    /// The reference entry with the given cite key, compared case-insensitively.
+    pub fn entry(&self, key: &str) -> Option<&EntryNode> {
+        let key = key.to_lowercase();
+        self.entries().find(|entry| {
+            entry
+                .key
+                .as_ref()
+                .is_some_and(|range| self.text(range).to_lowercase() == key)
+        })
+    }
+
+    /// The raw text of a field's value, including its delimiters.
+    pub fn field_value(&self, key: &str, name: &str) -> Option<&str> {
+        let entry = self.entry(key)?;
+        self.field(entry, name).map(|field| self.text(&field.value))
+    }
+
+    /// Set a field's value to `value`, which is inserted verbatim and so must
+    /// include its delimiters (e.g. `{New Title}`). A field that does not exist
+    /// is appended after the entry's last field, following its indentation and
+    /// trailing-comma style.
+    pub fn set_field(&mut self, key: &str, name: &str, value: &str) -> Result<()> {
+        let entry = self.expect_entry(key)?;
+        if let Some(field) = self.field(entry, name) {
+            let range = field.value.clone();
+            self.splice(range, value);
+            return Ok(());
+        }
+
+        // Without fields there is no closing line to reuse, so the new field
+        // gets its own trailing comma and newline.
+        let (anchor, indent, tail) = entry.fields.last().map_or_else(
+            || {
+                let key_end = entry.key.as_ref().map_or(entry.kind.end, |key| key.end);
+                (key_end, "    ".to_string(), Some(",\n"))
+            },
+            |last| (last.range.end, self.indent_of(last.range.start), None),
+        );
+        let field = format!("{indent}{name} = {value}");
+        let (at, text) = self.comma_after(anchor).map_or_else(
+            || (anchor, format!(",\n{field}{}", tail.unwrap_or_default())),
+            |comma| (comma + 1, format!("\n{field}{}", tail.unwrap_or(","))),
+        );
+        self.splice(at..at, &text);
+        Ok(())
+    }
+
+    /// Remove a field along with its separating comma. When the field sits on
+    /// its own line, the whole line is removed.
+    pub fn remove_field(&mut self, key: &str, name: &str) -> Result<()> {
+        let entry = self.expect_entry(key)?;
+        let field = self
+            .field(entry, name)
+            .ok_or_else(|| Error::UnknownField(key.to_string(), name.to_string()))?;
+
+        let mut start = field.range.start;
+        let comma = self.comma_after(field.range.end);
+        let mut end = comma.map_or(field.range.end, |comma| comma + 1);
+
+        let line_start = self.source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let rest = &self.source[end..];
+        let line_end = rest.find('\n').map_or(self.source.len(), |i| end + i + 1);
+        if self.source[line_start..start].trim().is_empty()
+            && self.source[end..line_end].trim().is_empty()
+        {
+            start = line_start;
+            end = line_end;
+        } else if comma.is_none() {
+            // The last field without a trailing comma takes the preceding
+            // separator with it instead.
+            let before = self.source[..start].trim_end();
+            if let Some(before) = before.strip_suffix(',') {
+                start = before.trim_end().len();
+            }
+        }
+        self.splice(start..end, "");
+        Ok(())
+    }
+
+    /// Change an entry's cite key, leaving the rest of the entry untouched.
+    pub fn rename_key(&mut self, key: &str, new_key: &str) -> Result<()> {
+        let entry = self.expect_entry(key)?;
+        let range = entry
+            .key
+            .clone()
+            .ok_or_else(|| Error::UnknownEntry(key.to_string()))?;
+        self.splice(range, new_key);
+        Ok(())
+    }
+
+    /// Replace an entire entry (from its `@` through its closing delimiter)
+    /// with `text`, e.g. the output of [`crate::format::Formatter::format_entry`].
+    pub fn replace_entry(&mut self, key: &str, text: &str) -> Result<()> {
+        let range = self.expect_entry(key)?.range.clone();
+        self.splice(range, text);
+        Ok(())
+    }
+
+    /// Remove an entry and the whitespace that follows it.
+    pub fn remove_entry(&mut self, key: &str) -> Result<()> {
+        let range = self.expect_entry(key)?.range.clone();
+        let rest = &self.source[range.end..];
+        let end = range.end + (rest.len() - rest.trim_start().len());
+        self.splice(range.start..end, "");
+        Ok(())
+    }
+
+    pub fn write(&self, filepath: &str) -> Result<()> {
+        fs::write(filepath, &self.source)?;
+        Ok(())
+    }
+
+    fn expect_entry(&self, key: &str) -> Result<&EntryNode> {
+        self.entry(key)
+            .ok_or_else(|| Error::UnknownEntry(key.to_string()))
+    }
+
+    fn field<'a>(&self, entry: &'a EntryNode, name: &str) -> Option<&'a FieldNode> {
+        let name = name.to_lowercase();
+        entry
+            .fields
+            .iter()
+            .find(|field| self.text(&field.name).to_lowercase() == name)
+    }
+
+    /// The offset of the comma following `offset`, skipping whitespace only.
+    fn comma_after(&self, offset: usize) -> Option<usize> {
+        let rest = &self.source[offset..];
+        let skipped = rest.len() - rest.trim_start().len();
+        rest[skipped..].starts_with(',').then_some(offset + skipped)
+    }
+
+    /// The whitespace between the start of the line and `offset`, or four
+    /// spaces when something other than whitespace precedes it.
+    fn indent_of(&self, offset: usize) -> String {
+        let line_start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let indent = &self.source[line_start..offset];
+        if indent.trim().is_empty() {
+            indent.to_string()
+        } else {
+            "    ".to_string()
+        }
+    }
+
+    /// Replace the text in `range` and rebuild the tree. Only the spliced range
+    /// of the source changes.
+    fn splice(&mut self, range: Range<usize>, text: &str) {
+        self.source.replace_range(range, text);
+        self.items = build(&self.source);
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> std::result::Result<(), fmt::Error> {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Index the source with entry nodes, filling the gaps between them with
+/// trivia so that the items cover every byte.
+fn build(source: &str) -> Vec<Item> {
+    let tokens = Tokenizer::new(source.chars()).tokenize();
+    let (entries, _) = Parser::new(tokens.into_iter()).parse_recovering();
+
+    let mut items = Vec::new();
+    let mut cursor = 0;
+    for entry in entries.iter() {
+        let Some(node) = entry_node(source, entry) else {
+            continue;
+        };
+        if node.range.start > cursor {
+            items.push(Item::Trivia(cursor..node.range.start));
+        }
+        cursor = node.range.end;
+        items.push(Item::Entry(node));
+    }
+    if cursor < source.len() {
+        items.push(Item::Trivia(cursor..source.len()));
+    }
+    items
+}
+
+fn entry_node(source: &str, entry: &EntryType) -> Option<EntryNode> {
+    let range = range(entry.span());
+    // The entry type follows the `@` and any whitespace.
+    let kind_start = skip_whitespace(source, range.start + 1);
+    let kind_len = source[kind_start..]
+        .find(|c: char| c.is_whitespace() || matches!(c, '{' | '('))
+        .unwrap_or(0);
+    let kind = kind_start..kind_start + kind_len;
+
+    let (key, fields) = match entry {
+        EntryType::RefEntry(e) => {
+            // The cite key follows the opening delimiter and any whitespace.
+            let open = skip_whitespace(source, kind.end);
+            let key_start = skip_whitespace(source, open + 1);
+            let key = key_start..key_start + e.key.len();
+            let fields = e
+                .tags
+                .iter()
+                .map(|tag| field_node(&tag.name, tag))
+                .collect();
+            (Some(key), fields)
+        }
+        EntryType::StringEntry(e) => (None, vec![field_node(&e.tag().name, e.tag())]),
+        EntryType::PreambleEntry(_) => (None, Vec::new()),
+        EntryType::CommentEntry(e) if e.kind() == CommentKind::Explicit => (None, Vec::new()),
+        EntryType::CommentEntry(_) | EntryType::RawEntry(_) => return None,
+    };
+
+    Some(EntryNode {
+        range,
+        kind,
+        key,
+        fields,
+    })
+}
+
+const fn field_node(name: &str, tag: &Tag) -> FieldNode {
+    let range = range(tag.span);
+    FieldNode {
+        name: range.start..range.start + name.len(),
+        value: self::range(tag.value_span),
+        range,
+    }
+}
+
+const fn range(span: Span) -> Range<usize> {
+    span.start.offset..span.end.offset
+}
+
+fn skip_whitespace(source: &str, offset: usize) -> usize {
+    let rest = &source[offset..];
+    offset + (rest.len() - rest.trim_start().len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "% header\n\n@Article{Smith2020,\n  Title = \"A  Title\",\n  year  = 2020,\n}\n\n@misc ( b , note = {x} )\n";
+
+    #[test]
+    fn test_round_trip_snippets() {
+        for entry in fs::read_dir("tests/snippets").unwrap() {
+            let path = entry.unwrap().path();
+            let source = fs::read_to_string(&path).unwrap();
+            let document = Document::parse(source.clone());
+            assert_eq!(document.to_string(), source, "{}", path.display());
+            let tiled: String = document
+                .items()
+                .iter()
+                .map(|item| match item {
+                    Item::Entry(entry) => document.text(&entry.range),
+                    Item::Trivia(range) => document.text(range),
+                })
+                .collect();
+            assert_eq!(tiled, source, "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn test_nodes() {
+        let document = Document::parse(SOURCE);
+        let entry = document.entry("smith2020").unwrap();
+        assert_eq!(document.text(&entry.kind), "Article");
+        assert_eq!(document.text(entry.key.as_ref().unwrap()), "Smith2020");
+        assert_eq!(document.text(&entry.fields[0].name), "Title");
+        assert_eq!(
+            document.field_value("Smith2020", "title"),
+            Some("\"A  Title\"")
+        );
+        assert_eq!(document.field_value("b", "note"), Some("{x}"));
+    }
+
+    #[test]
+    fn test_set_existing_field() -> Result<()> {
+        let mut document = Document::parse(SOURCE);
+        document.set_field("smith2020", "year", "{2021}")?;
+        assert_eq!(
+            document.to_string(),
+            SOURCE.replace("year  = 2020", "year  = {2021}")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_new_field() -> Result<()> {
+        let mut document = Document::parse(SOURCE);
+        document.set_field("smith2020", "doi", "{10.1/x}")?;
+        document.set_field("b", "year", "2020")?;
+        let expected = SOURCE
+            .replace("year  = 2020,\n", "year  = 2020,\n  doi = {10.1/x},\n")
+            .replace("note = {x} )", "note = {x},\n    year = 2020 )");
+        assert_eq!(document.to_string(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_field_without_fields() -> Result<()> {
+        let mut document = Document::parse("@misc{a}\n");
+        document.set_field("a", "title", "{T}")?;
+        assert_eq!(document.to_string(), "@misc{a,\n    title = {T},\n}\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_field() -> Result<()> {
+        let mut document = Document::parse(SOURCE);
+        document.remove_field("smith2020", "title")?;
+        document.remove_field("b", "note")?;
+        let expected = SOURCE
+            .replace("  Title = \"A  Title\",\n", "")
+            .replace(" , note = {x} )", " )");
+        assert_eq!(document.to_string(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_and_replace_entry() -> Result<()> {
+        let mut document = Document::parse(SOURCE);
+        document.rename_key("smith2020", "smith2020a")?;
+        document.replace_entry("b", "@misc{b}")?;
+        let expected = SOURCE
+            .replace("{Smith2020,", "{smith2020a,")
+            .replace("@misc ( b , note = {x} )", "@misc{b}");
+        assert_eq!(document.to_string(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_entry() -> Result<()> {
+        let mut document = Document::parse(SOURCE);
+        document.remove_entry("smith2020")?;
+        assert_eq!(
+            document.to_string(),
+            "% header\n\n@misc ( b , note = {x} )\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_entry_and_field() {
+        let mut document = Document::parse(SOURCE);
+        assert!(matches!(
+            document.set_field("missing", "title", "{T}"),
+            Err(Error::UnknownEntry(_))
+        ));
+        assert!(matches!(
+            document.remove_field("b", "title"),
+            Err(Error::UnknownField(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_malformed_entries_are_trivia() {
+        let source = "@misc{a, x}\n@misc{b}\n";
+        let document = Document::parse(source);
+        assert_eq!(document.entries().count(), 1);
+        assert_eq!(document.items()[0], Item::Trivia(0..12));
+        assert_eq!(document.to_string(), source);
+    }
+}
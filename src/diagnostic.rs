@@ -0,0 +1,263 @@
+use crate::token::Span;
+use crate::Error;
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+
+    // ANSI color for the label and the caret underline.
+    const fn color(self) -> &'static str {
+        match self {
+            Self::Error => "\x1b[1;31m",
+            Self::Warning => "\x1b[1;33m",
+        }
+    }
+}
+
+const BOLD: &str = "\x1b[1m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+const RESET: &str = "\x1b[0m";
+
+/// A message about a location in a source file, rendered in the style of
+/// rustc: the file path and position, the offending source line, a caret
+/// underline beneath the span, and an optional help hint.
+///
+/// ```text
+/// error: Expected `,`; found `year`
+///  --> refs.bib:3:15
+///   |
+/// 3 |   title = "A" year = 2020,
+///   |               ^^^^
+///   = help: missing comma after previous tag?
+/// ```
+#[derive(Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    span: Option<Span>,
+    help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message.into())
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message.into())
+    }
+
+    const fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            message,
+            span: None,
+            help: None,
+        }
+    }
+
+    pub const fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    pub const fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Render the diagnostic against the source text of the file at `path`.
+    /// ANSI colors are used only when `color` is set (e.g. stderr is a TTY).
+    /// The result ends without a trailing newline.
+    pub fn render(&self, path: &str, source: &str, color: bool) -> String {
+        let paint = |style: &str, text: &str| {
+            if color {
+                format!("{style}{text}{RESET}")
+            } else {
+                text.to_string()
+            }
+        };
+
+        let mut out = format!(
+            "{}{}",
+            paint(self.severity.color(), self.severity.label()),
+            paint(BOLD, &format!(": {}", self.message)),
+        );
+
+        let snippet = self.span.and_then(|span| snippet(source, span));
+        let gutter = snippet
+            .as_ref()
+            .map_or(1, |s| s.line_number.to_string().len());
+        let pad = " ".repeat(gutter);
+
+        match self.span {
+            Some(span) => write!(
+                out,
+                "\n{pad}{} {path}:{}:{}",
+                paint(BLUE, "-->"),
+                span.start.line,
+                span.start.column
+            ),
+            None => write!(out, "\n{pad}{} {path}", paint(BLUE, "-->")),
+        }
+        .expect("writing to a String cannot fail");
+
+        if let Some(snippet) = snippet {
+            let bar = paint(BLUE, "|");
+            let number = paint(BLUE, &snippet.line_number.to_string());
+            let carets = paint(self.severity.color(), &"^".repeat(snippet.width));
+            write!(
+                out,
+                "\n{pad} {bar}\n{number} {bar} {}\n{pad} {bar} {}{carets}",
+                snippet.line, snippet.indent
+            )
+            .expect("writing to a String cannot fail");
+        }
+
+        if let Some(help) = &self.help {
+            write!(out, "\n{pad} {} {help}", paint(CYAN, "= help:"))
+                .expect("writing to a String cannot fail");
+        }
+
+        out
+    }
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(error: &Error) -> Self {
+        let mut diagnostic = Self::error(error.message());
+        if let Some(span) = error.span() {
+            diagnostic = diagnostic.with_span(span);
+        }
+        if let Some(help) = error.help() {
+            diagnostic = diagnostic.with_help(help);
+        }
+        diagnostic
+    }
+}
+
+/// The source line containing the start of a span, with the whitespace needed
+/// to align a caret underline beneath the span.
+struct Snippet<'a> {
+    line_number: u32,
+    line: &'a str,
+    indent: String,
+    width: usize,
+}
+
+fn snippet(source: &str, span: Span) -> Option<Snippet<'_>> {
+    let offset = span.start.offset;
+    if offset > source.len() || !source.is_char_boundary(offset) {
+        return None;
+    }
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[offset..]
+        .find('\n')
+        .map_or(source.len(), |i| offset + i);
+    let line = source[line_start..line_end].trim_end_matches('\r');
+
+    // Keep tabs so the caret lines up however the terminal renders them.
+    let before = &line[..(offset - line_start).min(line.len())];
+    let indent = before
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    // Underline the span, clipped to the end of its first line, and always at
+    // least one caret so zero-width spans (e.g. end of input) stay visible.
+    let end = span.end.offset.clamp(offset, line_start + line.len());
+    let width = source
+        .get(offset..end)
+        .map_or(0, |s| s.chars().count())
+        .max(1);
+
+    Some(Snippet {
+        line_number: span.start.line,
+        line,
+        indent,
+        width,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::Parser;
+    use crate::token::{Position, Tokenizer};
+
+    fn first_error(source: &str) -> Error {
+        let tokens = Tokenizer::new(source.chars()).tokenize();
+        let (_, errors) = Parser::new(tokens.into_iter()).parse_recovering();
+        errors
+            .into_iter()
+            .next()
+            .expect("source should fail to parse")
+    }
+
+    #[test]
+    fn test_render_error_with_help() {
+        let source = "@misc{a,\n  title = \"A\" year = 2020,\n}\n";
+        let diagnostic = Diagnostic::from(&first_error(source));
+        let expected = "\
+error: Expected `,`; found `year`
+ --> refs.bib:2:15
+  |
+2 |   title = \"A\" year = 2020,
+  |               ^^^^
+  = help: missing comma after previous tag?";
+        assert_eq!(diagnostic.render("refs.bib", source, false), expected);
+    }
+
+    #[test]
+    fn test_render_aligns_tabs() {
+        let source = "@misc{a,\n\ttitle {A},\n}";
+        let rendered = Diagnostic::from(&first_error(source)).render("refs.bib", source, false);
+        assert!(
+            rendered.contains("2 | \ttitle {A},\n  | \t      ^\n"),
+            "{rendered}"
+        );
+    }
+
+    #[test]
+    fn test_render_end_of_input() {
+        let source = "@misc{a,\n  title = {A";
+        let rendered = Diagnostic::from(&first_error(source)).render("refs.bib", source, false);
+        assert!(rendered.starts_with("error: Unexpected end of token stream\n"));
+        assert!(rendered.contains("  = help: is a brace or quote left unclosed?"));
+    }
+
+    #[test]
+    fn test_render_warning_without_snippet() {
+        let diagnostic = Diagnostic::warning("something odd").with_span(Span::new(
+            Position::new(9, 1, 500),
+            Position::new(9, 2, 501),
+        ));
+        assert_eq!(
+            diagnostic.render("refs.bib", "short", false),
+            "warning: something odd\n --> refs.bib:9:1"
+        );
+    }
+
+    #[test]
+    fn test_render_color() {
+        let diagnostic = Diagnostic::error("bad");
+        let rendered = diagnostic.render("refs.bib", "", true);
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m\x1b[1m: bad\x1b[0m"));
+    }
+}
@@ -1,18 +1,27 @@
 use derive_more::From;
 
-use crate::token::{Position, Token, TokenInfo};
+use crate::token::{Position, Span, Special, Token, TokenInfo};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, From)]
 pub enum Error {
+    CrossrefCycle(Vec<String>),
+    CrossrefOrder(String, String, Span),
     EndOfTokenStream(Position),
     InternalAssertion(String),
+    InvalidConfig(String),
+    KeyCaseCollision(String, String, Span),
+    MacroCycle(Vec<String>),
     MissingCiteKey(TokenInfo),
     MissingContent(TokenInfo),
     MissingEntryType(TokenInfo),
     MissingTagName(TokenInfo),
+    UndefinedCrossref(String, Span),
+    UndefinedMacro(String, Span),
     UnexpectedToken(Token, TokenInfo),
+    UnknownEntry(String),
+    UnknownField(String, String),
 
     #[from]
     Custom(String),
@@ -25,6 +34,87 @@ impl Error {
     pub fn custom(val: impl std::fmt::Display) -> Self {
         Self::Custom(val.to_string())
     }
+
+    /// The source range the error points at, if it came from the parser.
+    pub const fn span(&self) -> Option<Span> {
+        match self {
+            Self::EndOfTokenStream(position) => Some(Span::new(*position, *position)),
+            Self::MissingCiteKey(info)
+            | Self::MissingContent(info)
+            | Self::MissingEntryType(info)
+            | Self::MissingTagName(info)
+            | Self::UnexpectedToken(_, info) => Some(Span::new(info.position, info.end())),
+            Self::CrossrefOrder(_, _, span)
+            | Self::KeyCaseCollision(_, _, span)
+            | Self::UndefinedCrossref(_, span)
+            | Self::UndefinedMacro(_, span)
+                if span.start.line > 0 =>
+            {
+                Some(*span)
+            }
+            _ => None,
+        }
+    }
+
+    /// The error message without its source position, for renderers that show
+    /// the position separately (see [`crate::diagnostic::Diagnostic`]).
+    pub fn message(&self) -> String {
+        match self {
+            Self::EndOfTokenStream(_) => "Unexpected end of token stream".to_string(),
+            Self::MissingCiteKey(info) => format!("Expected cite key; found `{}`", info.value),
+            Self::MissingContent(info) => format!("Expected tag content; found `{}`", info.value),
+            Self::MissingEntryType(info) => {
+                format!("Expected entry type; found `{}`", info.value)
+            }
+            Self::MissingTagName(info) => format!("Expected tag name; found `{}`", info.value),
+            Self::UnexpectedToken(expected, found) => {
+                format!("Expected `{}`; found `{}`", expected, found.value)
+            }
+            Self::CrossrefOrder(child, parent, _) => {
+                format!("Entry `{parent}` is cross-referenced by `{child}` but appears before it")
+            }
+            Self::KeyCaseCollision(key, first, _) => {
+                format!("Cite key `{key}` differs only in case from `{first}`")
+            }
+            Self::UndefinedCrossref(key, _) => {
+                format!("Cross-referenced entry `{key}` is not defined")
+            }
+            Self::UndefinedMacro(name, _) => format!("Undefined @string macro `{name}`"),
+            _ => self.to_string(),
+        }
+    }
+
+    /// A hint at the likely cause of the error, if one is known.
+    pub const fn help(&self) -> Option<&'static str> {
+        match self {
+            Self::EndOfTokenStream(_) => Some("is a brace or quote left unclosed?"),
+            Self::MissingCiteKey(_) => Some("entries start with a cite key, e.g. `@article{key,`"),
+            Self::MissingContent(_) => Some(
+                "tag content is a `{braced}` or `\"quoted\"` string, a number, or a @string name",
+            ),
+            Self::MissingEntryType(_) => Some("entries start with a type, e.g. `@article`"),
+            Self::MissingTagName(_) => Some("tags take the form `name = value`"),
+            Self::CrossrefOrder(_, _, _) => {
+                Some("BibTeX only inherits from parents that appear after their children")
+            }
+            Self::KeyCaseCollision(_, _, _) => {
+                Some("cite keys are case-sensitive in biber; rename one of the entries")
+            }
+            Self::UndefinedMacro(_, _) => {
+                Some("define it with `@string{name = \"text\"}` or quote the value")
+            }
+            Self::UnexpectedToken(Token::Special(Special::Comma), found) if found.is_value() => {
+                Some("missing comma after previous tag?")
+            }
+            Self::UnexpectedToken(Token::Special(Special::Equals), _) => {
+                Some("tag names must be followed by `=`")
+            }
+            Self::UnexpectedToken(Token::Special(Special::BraceLeft), _) => {
+                Some("entry bodies are delimited by braces or parentheses")
+            }
+            _ => None,
+        }
+    }
 }
 
 impl core::fmt::Display for Error {
@@ -36,6 +126,27 @@ impl core::fmt::Display for Error {
             Self::InternalAssertion(message) => {
                 write!(fmt, "Internal assertion error: {message}")
             }
+            Self::CrossrefCycle(keys) => {
+                write!(fmt, "Entries cross-reference each other in a cycle: ")?;
+                write!(fmt, "{}", keys.join(" -> "))
+            }
+            Self::CrossrefOrder(_, _, span)
+            | Self::KeyCaseCollision(_, _, span)
+            | Self::UndefinedCrossref(_, span)
+                if span.start.line > 0 =>
+            {
+                write!(fmt, "{} at {}", self.message(), span.start)
+            }
+            Self::CrossrefOrder(_, _, _)
+            | Self::KeyCaseCollision(_, _, _)
+            | Self::UndefinedCrossref(_, _) => {
+                write!(fmt, "{}", self.message())
+            }
+            Self::InvalidConfig(message) => write!(fmt, "Invalid configuration: {message}"),
+            Self::MacroCycle(names) => {
+                write!(fmt, "@string macros refer to each other in a cycle: ")?;
+                write!(fmt, "{}", names.join(" -> "))
+            }
             Self::MissingCiteKey(info) => write!(
                 fmt,
                 "Expected cite key at {}; found `{}`",
@@ -61,6 +172,14 @@ impl core::fmt::Display for Error {
                 "Expected `{}` at {}; found `{}`",
                 expected, found.position, found.value,
             ),
+            Self::UndefinedMacro(name, span) if span.start.line > 0 => {
+                write!(fmt, "Undefined @string macro `{name}` at {}", span.start)
+            }
+            Self::UndefinedMacro(name, _) => write!(fmt, "Undefined @string macro `{name}`"),
+            Self::UnknownEntry(key) => write!(fmt, "No entry with cite key `{key}`"),
+            Self::UnknownField(key, name) => {
+                write!(fmt, "Entry `{key}` has no field `{name}`")
+            }
             _ => write!(fmt, "{self:?}"),
         }
     }
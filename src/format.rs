@@ -1,13 +1,21 @@
-use crate::models::{CommentEntry, CommentKind, Entries, EntryType, PreambleEntry, RefEntry};
-use crate::models::{Part, Sequence, StringEntry, Tag, Value};
+use crate::case::{Case, KeyCase};
+use crate::models::is_verbatim;
+use crate::models::{CommentEntry, CommentKind, Entries, EntryType, NameStyle, PreambleEntry};
+use crate::models::{Delimiter, Part, RawEntry, RefEntry, Sequence, StringEntry, Tag, Value};
+use crate::order::{EntrySort, KeyCollation, TagOrder};
 use crate::Result;
-use std::cmp::Ordering;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
 use std::mem::discriminant;
+use std::str::FromStr;
 
 struct Group<'a> {
-    comments: Vec<&'a EntryType>, // CommentEntry variants only
+    comments: Vec<&'a EntryType>, // CommentEntry and RawEntry variants only
     entry: Option<&'a EntryType>, // None only for the trailing group
 }
 
@@ -18,6 +26,20 @@ pub struct Formatter {
     skip_empty_tags: bool,
     sort_entries: bool,
     sort_tags: bool,
+    entry_sort: EntrySort,
+    key_collation: KeyCollation,
+    key_case: KeyCase,
+    entry_type_case: Case,
+    keyword_case: Case,
+    field_name_case: Case,
+    indent: Indent,
+    align_values: bool,
+    delimiters: Option<DelimiterStyle>,
+    max_width: Option<usize>,
+    trailing_comma: bool,
+    normalize_pages: bool,
+    normalize_names: Option<NameStyle>,
+    tag_order: TagOrder,
 }
 
 impl Formatter {
@@ -48,11 +70,12 @@ impl Formatter {
             .collect();
 
         // Attach each run of comments to the next non-comment entry; leftovers
-        // form a trailing entry-less group that must stay last.
+        // form a trailing entry-less group that must stay last. Raw (malformed)
+        // entries are opaque text, so they travel the same way comments do.
         let mut groups: Vec<Group> = Vec::new();
         let mut pending: Vec<&EntryType> = Vec::new();
         for entry in entries {
-            if matches!(entry, EntryType::CommentEntry(_)) {
+            if is_comment_like(entry) {
                 pending.push(entry);
             } else {
                 groups.push(Group {
@@ -69,11 +92,12 @@ impl Formatter {
         }
 
         groups.sort_by(|a, b| match (a.entry, b.entry) {
-            (Some(x), Some(y)) => x.cmp(y), // derived EntryType Ord, unchanged
+            (Some(x), Some(y)) => self.compare_entries(x, y),
             (Some(_), None) => Ordering::Less,
             (None, Some(_)) => Ordering::Greater,
             (None, None) => Ordering::Equal,
         });
+        let groups = order_crossrefs(groups);
 
         let mut out = String::new();
         for (i, group) in groups.iter().enumerate() {
@@ -94,6 +118,25 @@ impl Formatter {
         out
     }
 
+    /// Order reference entries by the configured sort mode, breaking ties by
+    /// the emitted cite key. Other entries keep the derived `EntryType` order,
+    /// which puts preambles and strings first.
+    fn compare_entries(&self, a: &EntryType, b: &EntryType) -> Ordering {
+        match (a, b) {
+            (EntryType::RefEntry(a), EntryType::RefEntry(b)) => {
+                self.entry_sort.compare(a, b).then_with(|| {
+                    if self.entry_sort.by_key() {
+                        self.key_collation
+                            .compare(&self.format_key(&a.key), &self.format_key(&b.key))
+                    } else {
+                        Ordering::Equal
+                    }
+                })
+            }
+            _ => a.cmp(b),
+        }
+    }
+
     /// Emit entries in their original order, reproducing the source whitespace
     /// that preceded each element so nothing is reflowed when sorting is
     /// disabled.
@@ -118,9 +161,9 @@ impl Formatter {
         }
         // No captured whitespace: a comment block is set off by a blank line and
         // sits flush above its following entry, matching the sorted path.
-        if matches!(cur, EntryType::CommentEntry(_)) {
+        if is_comment_like(cur) {
             "\n\n".to_string()
-        } else if matches!(prev, EntryType::CommentEntry(_)) {
+        } else if is_comment_like(prev) {
             "\n".to_string()
         } else if discriminant(prev) != discriminant(cur) || matches!(cur, EntryType::RefEntry(_)) {
             "\n\n".to_string()
@@ -146,6 +189,7 @@ impl Formatter {
         match entry {
             EntryType::CommentEntry(e) => self.format_comment_entry(e),
             EntryType::PreambleEntry(e) => self.format_preamble_entry(e),
+            EntryType::RawEntry(e) => self.format_raw_entry(e),
             EntryType::RefEntry(e) => self.format_ref_entry(e),
             EntryType::StringEntry(e) => self.format_string_entry(e),
         }
@@ -153,14 +197,24 @@ impl Formatter {
 
     pub fn format_comment_entry(&self, entry: &CommentEntry) -> String {
         match entry.kind() {
-            CommentKind::Explicit => format!("@COMMENT{{{}}}", entry.body()),
+            CommentKind::Explicit => format!(
+                "@{}{{{}}}",
+                self.format_keyword(entry.keyword(), "comment"),
+                entry.body()
+            ),
             CommentKind::Implicit => entry.body().to_string(),
         }
     }
 
+    /// Emit a malformed entry exactly as it appeared in the source.
+    pub fn format_raw_entry(&self, entry: &RawEntry) -> String {
+        entry.body().to_string()
+    }
+
     pub fn format_preamble_entry(&self, entry: &PreambleEntry) -> String {
         format!(
-            "@PREAMBLE{{{}}}",
+            "@{}{{{}}}",
+            self.format_keyword(entry.keyword(), "preamble"),
             self.format_value_sequence("preamble", entry.body())
         )
     }
@@ -179,51 +233,135 @@ impl Formatter {
         if tags.is_empty() {
             return format!(
                 "@{}{{{}}}",
-                entry.kind.to_lowercase(),
-                entry.key.to_lowercase()
+                self.entry_type_case.apply(&entry.kind),
+                self.format_key(&entry.key)
             );
         }
 
         if self.sort_tags {
-            tags.sort();
+            self.tag_order.sort(&entry.kind, &mut tags);
         }
 
         let mut formatted = String::new();
         formatted.push_str(&format!(
             "@{}{{{},\n",
-            entry.kind.to_lowercase(),
-            entry.key.to_lowercase()
+            self.entry_type_case.apply(&entry.kind),
+            self.format_key(&entry.key)
         ));
-        for tag in &tags {
-            formatted.push_str(&format!("    {},\n", self.format_tag(tag)));
+        // Pad names to the longest one so the `=` signs line up.
+        let width = if self.align_values {
+            tags.iter()
+                .map(|tag| self.field_name_case.apply(&tag.name).chars().count())
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        for (i, tag) in tags.iter().enumerate() {
+            let comma = if i + 1 < tags.len() || self.trailing_comma {
+                ","
+            } else {
+                ""
+            };
+            let line = format!(
+                "{}{}{comma}",
+                self.indent,
+                self.format_tag_padded(tag, width)
+            );
+            match self.max_width {
+                // Verbatim fields such as `url` are typeset as written, so a
+                // line break would change them.
+                Some(max_width)
+                    if matches!(tag.value, Value::Single(_)) && !is_verbatim(&tag.name) =>
+                {
+                    formatted.push_str(&wrap_value(&line, max_width));
+                }
+                _ => formatted.push_str(&line),
+            }
+            formatted.push('\n');
         }
         formatted.push('}');
         formatted
     }
 
+    /// The cite key as it is written out.
+    pub fn format_key(&self, key: &str) -> String {
+        self.key_case.apply(key)
+    }
+
+    /// The cite keys of `entries` that are written out differently from the
+    /// source, as `(original, formatted)` pairs, so that citations can be
+    /// updated to match.
+    pub fn changed_keys(&self, entries: &Entries) -> Vec<(String, String)> {
+        let mut changed: Vec<(String, String)> = Vec::new();
+        for entry in entries.iter() {
+            let EntryType::RefEntry(e) = entry else {
+                continue;
+            };
+            let key = self.format_key(&e.key);
+            if key != e.key && !changed.iter().any(|(original, _)| *original == e.key) {
+                changed.push((e.key.clone(), key));
+            }
+        }
+        changed
+    }
+
     pub fn format_string_entry(&self, entry: &StringEntry) -> String {
         let tag = entry.tag();
+        let delimiter = self.delimiter(tag, Delimiter::Quotes);
         let value = match &tag.value {
-            Value::Single(s) => format!("\"{s}\""),
-            Value::Integer(v) => format!("\"{v}\""),
+            Value::Single(s) => delimit(s, delimiter),
+            Value::Integer(v) => delimit(&v.to_string(), delimiter),
             Value::Sequence(s) => self.format_value_sequence(&tag.name, s),
         };
-        format!("@STRING{{{} = {}}}", tag.name.to_lowercase(), value)
+        format!(
+            "@{}{{{} = {}}}",
+            self.format_keyword(entry.keyword(), "string"),
+            tag.name.to_lowercase(),
+            value
+        )
     }
 
     pub fn format_tag(&self, tag: &Tag) -> String {
-        let name = tag.name.to_lowercase();
-        let value = self.format_value(&name, &tag.value);
-        format!("{} = {}", name, value)
+        self.format_tag_padded(tag, 0)
+    }
+
+    /// Format a tag with its name padded to `width` characters.
+    fn format_tag_padded(&self, tag: &Tag, width: usize) -> String {
+        let name = self.field_name_case.apply(&tag.name);
+        let delimiter = self.delimiter(tag, Delimiter::Braces);
+        let value = self.format_value(&tag.name.to_lowercase(), &tag.value, delimiter);
+        format!("{name:<width$} = {value}")
+    }
+
+    /// The delimiter for a tag's value under the configured style, where
+    /// `default` is the delimiter used when no style is configured.
+    fn delimiter(&self, tag: &Tag, default: Delimiter) -> Delimiter {
+        match self.delimiters {
+            None => default,
+            Some(DelimiterStyle::Braces) => Delimiter::Braces,
+            Some(DelimiterStyle::Quotes) => Delimiter::Quotes,
+            Some(DelimiterStyle::Preserve) => tag.delimiter.unwrap_or(default),
+        }
     }
 
-    fn format_value(&self, name: &str, value: &Value) -> String {
+    /// The keyword of a `@string`, `@preamble`, or `@comment` entry, using
+    /// `name` for entries with no keyword recorded from the source.
+    fn format_keyword(&self, written: Option<&str>, name: &str) -> String {
+        self.keyword_case.apply(written.unwrap_or(name))
+    }
+
+    fn format_value(&self, name: &str, value: &Value, delimiter: Delimiter) -> String {
         match value {
             Value::Single(s) => {
                 if self.preserve_tag_casing(name) {
-                    format!("{{{}}}", format_title(s))
+                    delimit(&format_title(s), delimiter)
+                } else if self.normalize_pages && name == "pages" {
+                    delimit(&normalize_pages(s), delimiter)
+                } else if let (Some(style), "author" | "editor") = (self.normalize_names, name) {
+                    delimit(&style.format(s), delimiter)
                 } else {
-                    format!("{{{s}}}")
+                    delimit(s, delimiter)
                 }
             }
             Value::Integer(s) => format!("{s}"),
@@ -243,9 +381,9 @@ impl Formatter {
         match part {
             Part::Quoted(s) => {
                 if self.preserve_tag_casing(name) {
-                    format!("\"{}\"", format_title(s))
+                    delimit(&format_title(s), Delimiter::Quotes)
                 } else {
-                    format!("\"{s}\"")
+                    delimit(s, Delimiter::Quotes)
                 }
             }
             Part::Value(v) => v.to_lowercase(),
@@ -257,12 +395,98 @@ impl Formatter {
     }
 }
 
+/// Reorder sorted groups so that every crossref parent follows all of its
+/// children, as BibTeX only inherits from parents defined after the entries
+/// citing them. This is a topological sort that always takes the earliest
+/// available group in sorted order, so groups unconstrained by crossrefs keep
+/// their sorted positions relative to one another. Groups in a crossref cycle
+/// cannot be ordered and are left in sorted order.
+fn order_crossrefs(groups: Vec<Group>) -> Vec<Group> {
+    // The first group with a key wins, as for duplicate keys BibTeX only sees
+    // the first entry.
+    let mut index: HashMap<String, usize> = HashMap::with_capacity(groups.len());
+    for (i, group) in groups.iter().enumerate() {
+        if let Some(EntryType::RefEntry(e)) = group.entry {
+            index.entry(e.key.to_lowercase()).or_insert(i);
+        }
+    }
+
+    // waiting[p] counts the groups whose entries crossref group p's entry and
+    // are not yet emitted.
+    let mut waiting: Vec<usize> = vec![0; groups.len()];
+    let mut parents: Vec<Option<usize>> = vec![None; groups.len()];
+    for (i, group) in groups.iter().enumerate() {
+        let Some(EntryType::RefEntry(e)) = group.entry else {
+            continue;
+        };
+        let Some(&p) = e.crossref().and_then(|key| index.get(&key.to_lowercase())) else {
+            continue;
+        };
+        if p != i {
+            waiting[p] += 1;
+            parents[i] = Some(p);
+        }
+    }
+    if parents.iter().all(Option::is_none) {
+        return groups;
+    }
+
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..groups.len())
+        .filter(|&i| waiting[i] == 0)
+        .map(Reverse)
+        .collect();
+    let mut emitted = vec![false; groups.len()];
+    let mut order = Vec::with_capacity(groups.len());
+    // Groups before the cursor are all emitted.
+    let mut cursor = 0;
+    while order.len() < groups.len() {
+        let next = if let Some(Reverse(i)) = ready.pop() {
+            i
+        } else {
+            // Fall back to the earliest remaining group when a cycle blocks
+            // all.
+            while emitted[cursor] {
+                cursor += 1;
+            }
+            cursor
+        };
+        emitted[next] = true;
+        order.push(next);
+        if let Some(p) = parents[next] {
+            waiting[p] -= 1;
+            if waiting[p] == 0 && !emitted[p] {
+                ready.push(Reverse(p));
+            }
+        }
+    }
+
+    let mut groups: Vec<Option<Group>> = groups.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|i| groups[i].take().expect("each group is emitted once"))
+        .collect()
+}
+
 pub struct FormatterBuilder {
     format_title: bool,
     remove_comments: bool,
     skip_empty_tags: bool,
     sort_entries: bool,
     sort_tags: bool,
+    entry_sort: EntrySort,
+    key_collation: KeyCollation,
+    key_case: KeyCase,
+    entry_type_case: Case,
+    keyword_case: Case,
+    field_name_case: Case,
+    indent: Indent,
+    align_values: bool,
+    delimiters: Option<DelimiterStyle>,
+    max_width: Option<usize>,
+    trailing_comma: bool,
+    normalize_pages: bool,
+    normalize_names: Option<NameStyle>,
+    tag_order: TagOrder,
 }
 
 impl Default for FormatterBuilder {
@@ -273,6 +497,20 @@ impl Default for FormatterBuilder {
             skip_empty_tags: true,
             sort_entries: true,
             sort_tags: true,
+            entry_sort: EntrySort::default(),
+            key_collation: KeyCollation::default(),
+            key_case: KeyCase::default(),
+            entry_type_case: Case::Lower,
+            keyword_case: Case::Upper,
+            field_name_case: Case::Lower,
+            indent: Indent::default(),
+            align_values: false,
+            delimiters: None,
+            max_width: None,
+            trailing_comma: true,
+            normalize_pages: false,
+            normalize_names: None,
+            tag_order: TagOrder::default(),
         }
     }
 }
@@ -282,13 +520,27 @@ impl FormatterBuilder {
         Self::default()
     }
 
-    pub const fn build(self) -> Formatter {
+    pub fn build(self) -> Formatter {
         Formatter {
             format_title: self.format_title,
             remove_comments: self.remove_comments,
             skip_empty_tags: self.skip_empty_tags,
             sort_entries: self.sort_entries,
             sort_tags: self.sort_tags,
+            entry_sort: self.entry_sort,
+            key_collation: self.key_collation,
+            key_case: self.key_case,
+            entry_type_case: self.entry_type_case,
+            keyword_case: self.keyword_case,
+            field_name_case: self.field_name_case,
+            indent: self.indent,
+            align_values: self.align_values,
+            delimiters: self.delimiters,
+            max_width: self.max_width,
+            trailing_comma: self.trailing_comma,
+            normalize_pages: self.normalize_pages,
+            normalize_names: self.normalize_names,
+            tag_order: self.tag_order,
         }
     }
 
@@ -316,6 +568,394 @@ impl FormatterBuilder {
         self.sort_tags = sort_tags;
         self
     }
+
+    /// The order reference entries are sorted into when `sort_entries` is set.
+    pub const fn entry_sort(mut self, entry_sort: EntrySort) -> Self {
+        self.entry_sort = entry_sort;
+        self
+    }
+
+    /// Compare runs of digits in cite keys by value when sorting, so `paper9`
+    /// sorts before `paper10`.
+    pub const fn natural_key_sort(mut self, natural_key_sort: bool) -> Self {
+        self.key_collation.natural = natural_key_sort;
+        self
+    }
+
+    /// Ignore case and accents in cite keys when sorting.
+    pub const fn unicode_key_sort(mut self, unicode_key_sort: bool) -> Self {
+        self.key_collation.unicode = unicode_key_sort;
+        self
+    }
+
+    pub const fn key_case(mut self, key_case: KeyCase) -> Self {
+        self.key_case = key_case;
+        self
+    }
+
+    /// How entry types are cased, e.g. `@inproceedings` (the default).
+    pub const fn entry_type_case(mut self, entry_type_case: Case) -> Self {
+        self.entry_type_case = entry_type_case;
+        self
+    }
+
+    /// How the `@string`, `@preamble`, and `@comment` keywords are cased, e.g.
+    /// `@STRING` (the default).
+    pub const fn keyword_case(mut self, keyword_case: Case) -> Self {
+        self.keyword_case = keyword_case;
+        self
+    }
+
+    /// How field names are cased, e.g. `author` (the default).
+    pub const fn field_name_case(mut self, field_name_case: Case) -> Self {
+        self.field_name_case = field_name_case;
+        self
+    }
+
+    /// The indentation of the tags in an entry (default: four spaces).
+    pub const fn indent(mut self, indent: Indent) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Pad tag names so that the `=` signs in an entry line up.
+    pub const fn align_values(mut self, align_values: bool) -> Self {
+        self.align_values = align_values;
+        self
+    }
+
+    /// The delimiters around string values. By default field values use
+    /// braces and `@string` definitions use quotes.
+    pub const fn delimiters(mut self, delimiters: DelimiterStyle) -> Self {
+        self.delimiters = Some(delimiters);
+        self
+    }
+
+    /// Wrap string values at spaces so that lines are at most `max_width`
+    /// characters where possible.
+    pub const fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Whether the last tag in an entry is followed by a comma (default: true).
+    pub const fn trailing_comma(mut self, trailing_comma: bool) -> Self {
+        self.trailing_comma = trailing_comma;
+        self
+    }
+
+    /// Rewrite page ranges in `pages` fields with the `--` BibTeX expects
+    /// (see [`normalize_pages`]).
+    pub const fn normalize_pages(mut self, normalize_pages: bool) -> Self {
+        self.normalize_pages = normalize_pages;
+        self
+    }
+
+    /// Rewrite `author` and `editor` lists as "von Last, Jr, First" names in
+    /// `style` (see [`NameStyle`]).
+    pub const fn normalize_names(mut self, style: NameStyle) -> Self {
+        self.normalize_names = Some(style);
+        self
+    }
+
+    /// The order tags are sorted into when `sort_tags` is set.
+    pub fn tag_order(mut self, tag_order: TagOrder) -> Self {
+        self.tag_order = tag_order;
+        self
+    }
+}
+
+/// The delimiters written around string values.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum DelimiterStyle {
+    /// `{...}`
+    Braces,
+    /// `"..."`, with quotes in the value protected by braces.
+    Quotes,
+    /// Whichever the value was written with in the source.
+    Preserve,
+}
+
+/// The indentation of the tags in an entry: a number of spaces or a tab. Read
+/// from `4` or `tab`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "IndentSetting")]
+pub enum Indent {
+    Spaces(usize),
+    Tab,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Self::Spaces(4)
+    }
+}
+
+impl fmt::Display for Indent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Spaces(width) => write!(f, "{:width$}", ""),
+            Self::Tab => f.write_str("\t"),
+        }
+    }
+}
+
+impl FromStr for Indent {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("tab") {
+            return Ok(Self::Tab);
+        }
+        s.parse()
+            .map(Self::Spaces)
+            .map_err(|_| format!("expected a number of spaces or `tab`, found `{s}`"))
+    }
+}
+
+/// An indent as written in a configuration file, either a number or a string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IndentSetting {
+    Width(usize),
+    Name(String),
+}
+
+impl TryFrom<IndentSetting> for Indent {
+    type Error = String;
+
+    fn try_from(setting: IndentSetting) -> std::result::Result<Self, Self::Error> {
+        match setting {
+            IndentSetting::Width(width) => Ok(Self::Spaces(width)),
+            IndentSetting::Name(name) => name.parse(),
+        }
+    }
+}
+
+/// Whether an element is opaque text positioned relative to the entry that
+/// follows it rather than sorted on its own.
+const fn is_comment_like(entry: &EntryType) -> bool {
+    matches!(entry, EntryType::CommentEntry(_) | EntryType::RawEntry(_))
+}
+
+/// Wrap `text` in `delimiter`, protecting quotes that would otherwise end a
+/// quoted value.
+fn delimit(text: &str, delimiter: Delimiter) -> String {
+    match delimiter {
+        Delimiter::Braces => format!("{{{text}}}"),
+        Delimiter::Quotes => format!("\"{}\"", escape_quotes(text)),
+    }
+}
+
+/// Brace-protect the quotes outside braces in `text`, e.g. `5" disk` becomes
+/// `5{"} disk` and `G\"odel` becomes `G{\"o}del`.
+fn escape_quotes(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '\\' if depth == 0 && chars.peek() == Some(&'"') => {
+                // An umlaut accent: brace it together with the letter it
+                // accents, which is a single letter or a braced group. A
+                // stray accent is braced alone, leaving what follows in place.
+                chars.next();
+                escaped.push_str("{\\\"");
+                if let Some(letter) = chars.next_if(|&c| c.is_alphabetic()) {
+                    escaped.push(letter);
+                } else {
+                    let mut group = 0usize;
+                    while let Some(c) = chars.next_if(|&c| group > 0 || c == '{') {
+                        escaped.push(c);
+                        match c {
+                            '{' => group += 1,
+                            '}' => group -= 1,
+                            _ => (),
+                        }
+                        if group == 0 {
+                            break;
+                        }
+                    }
+                }
+                escaped.push('}');
+                continue;
+            }
+            '"' if depth == 0 => {
+                escaped.push_str("{\"}");
+                continue;
+            }
+            _ => (),
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The columns a tab advances to the next multiple of when measuring lines.
+const TAB_WIDTH: usize = 8;
+
+/// Wrap a `name = {value}` line, which may end with a comma, to at most
+/// `max_width` columns by breaking the value at spaces, continuing it on lines
+/// aligned with its first character. Tabs in the indent count to the next tab
+/// stop (see [`TAB_WIDTH`]). Spaces inside `{...}` groups are never broken,
+/// and a word longer than the width (e.g. a URL) overflows rather than being
+/// split. Since the parser collapses whitespace, the wrapped value reads back
+/// unchanged.
+fn wrap_value(line: &str, max_width: usize) -> String {
+    if display_width(line) <= max_width {
+        return line.to_string();
+    }
+    let Some(equals) = line.find(" = ") else {
+        return line.to_string();
+    };
+    // The name, `=`, and opening delimiter; the value; the closing delimiter
+    // and any comma.
+    let (head, rest) = line.split_at(equals + " = ".len() + 1);
+    let tail_len = if rest.ends_with(',') { 2 } else { 1 };
+    let (body, tail) = rest.split_at(rest.len() - tail_len);
+    let continuation: String = head
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let words = split_outside_braces(body);
+    let mut wrapped = head.to_string();
+    let mut width = display_width(head);
+    for (i, word) in words.iter().enumerate() {
+        let mut needed = word.chars().count();
+        if i + 1 == words.len() {
+            needed += tail.chars().count();
+        }
+        if i > 0 {
+            if width + 1 + needed > max_width {
+                wrapped.push('\n');
+                wrapped.push_str(&continuation);
+                width = display_width(&continuation);
+            } else {
+                wrapped.push(' ');
+                width += 1;
+            }
+        }
+        wrapped.push_str(word);
+        width += word.chars().count();
+    }
+    wrapped.push_str(tail);
+    wrapped
+}
+
+/// The number of columns `text` spans, with tabs advancing to the next tab
+/// stop.
+fn display_width(text: &str) -> usize {
+    text.chars().fold(0, |width, c| {
+        if c == '\t' {
+            (width / TAB_WIDTH + 1) * TAB_WIDTH
+        } else {
+            width + 1
+        }
+    })
+}
+
+/// Split `text` at the spaces outside braces.
+fn split_outside_braces(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ' ' if depth == 0 => {
+                if start < i {
+                    words.push(&text[start..i]);
+                }
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Write the page ranges in a `pages` value with a double hyphen.
+///
+/// For example, `409-418`, `409–418`, `409 --- 418`, and `pp. 409-418` all
+/// become `409--418`. Ranges in a list are each normalized, keeping the
+/// separators as written; a comma without a following space only separates
+/// ranges if every piece is a range, so `1,234-1,240` is one range. Single
+/// pages, including article numbers such as `e1234` and roman numerals, and
+/// anything that is not a simple range are left as they are.
+pub fn normalize_pages(text: &str) -> String {
+    let text = text.trim();
+    let pages = ["pp.", "pp ", "p."]
+        .iter()
+        .find_map(|prefix| {
+            text.get(..prefix.len())
+                .filter(|start| start.eq_ignore_ascii_case(prefix))
+                .map(|_| text[prefix.len()..].trim_start())
+        })
+        .unwrap_or(text);
+    if pages.is_empty() {
+        return text.to_string();
+    }
+    let mut normalized = String::new();
+    let mut rest = pages;
+    while let Some((i, separator)) = next_list_separator(rest) {
+        normalized.push_str(&normalize_page_list(&rest[..i]));
+        normalized.push_str(separator);
+        rest = &rest[i + separator.len()..];
+    }
+    normalized.push_str(&normalize_page_list(rest));
+    normalized
+}
+
+/// The position and text of the first comma followed by whitespace, with
+/// that whitespace.
+fn next_list_separator(text: &str) -> Option<(usize, &str)> {
+    text.match_indices(',').find_map(|(i, _)| {
+        let after = &text[i + 1..];
+        let spaces = after.len() - after.trim_start().len();
+        (spaces > 0).then(|| (i, &text[i..=i + spaces]))
+    })
+}
+
+/// Normalize a piece of a page list with no comma-and-space separators: a
+/// single range, or ranges joined by bare commas if every piece is a range.
+fn normalize_page_list(text: &str) -> String {
+    let normalize =
+        |range: &str| split_page_range(range).map(|(first, last)| format!("{first}--{last}"));
+    if text.contains(',') {
+        let ranges: Option<Vec<String>> = text.split(',').map(normalize).collect();
+        if let Some(ranges) = ranges {
+            return ranges.join(",");
+        }
+    }
+    normalize(text).unwrap_or_else(|| text.to_string())
+}
+
+/// The first and last page of a range such as `409-418` or `409 – 418`.
+fn split_page_range(range: &str) -> Option<(&str, &str)> {
+    let is_dash = |c: char| matches!(c, '-' | '‐' | '–' | '—' | '−');
+    let range = range.trim();
+    let (first, rest) = range.split_once(is_dash)?;
+    let last = rest.trim_start_matches(|c: char| is_dash(c) || c.is_whitespace());
+    let first = first.trim_end();
+    (is_page(first) && is_page(last)).then_some((first, last))
+}
+
+/// Whether `page` is a single page: letters and digits, with digits optionally
+/// grouped in thousands, e.g. `e1234`, `xii`, or `1,234`.
+fn is_page(page: &str) -> bool {
+    let mut groups = page.split(',');
+    let first = groups.next().unwrap_or_default();
+    !first.is_empty()
+        && first.chars().all(char::is_alphanumeric)
+        && groups.all(|group| group.len() == 3 && group.chars().all(|c| c.is_ascii_digit()))
 }
 
 pub fn remove_braces(text: &str) -> String {
@@ -406,6 +1046,7 @@ pub fn format_title(text: &str) -> String {
 mod tests {
     use super::*;
     use crate::models::{CommentEntry, Entries, RefEntry};
+    use crate::token::Span;
     use test_case::test_case;
 
     fn ref_entry(key: &str) -> EntryType {
@@ -457,6 +1098,240 @@ mod tests {
         assert_eq!(formatter.format_entries(&entries), expected);
     }
 
+    fn crossref_entry(key: &str, parent: &str) -> EntryType {
+        let tag = Tag::new("crossref".to_string(), Value::Single(parent.to_string()));
+        EntryType::RefEntry(RefEntry::new(
+            "misc".to_string(),
+            key.to_string(),
+            vec![tag],
+        ))
+    }
+
+    #[test]
+    fn test_format_entries_sorts_on_emitted_keys() {
+        let entries = Entries::new(vec![
+            ref_entry("paper10"),
+            ref_entry("Smith2020"),
+            ref_entry("paper9"),
+            ref_entry("adams2019"),
+        ]);
+        let formatter = Formatter::builder().build();
+        assert_eq!(
+            formatter.format_entries(&entries),
+            "@misc{adams2019}\n\n@misc{paper10}\n\n@misc{paper9}\n\n@misc{smith2020}"
+        );
+        let formatter = Formatter::builder().natural_key_sort(true).build();
+        assert_eq!(
+            formatter.format_entries(&entries),
+            "@misc{adams2019}\n\n@misc{paper9}\n\n@misc{paper10}\n\n@misc{smith2020}"
+        );
+    }
+
+    #[test]
+    fn test_key_case() {
+        let entries = Entries::new(vec![ref_entry("Smith2020"), ref_entry("adams2019")]);
+        let formatter = Formatter::builder().build();
+        assert_eq!(
+            formatter.changed_keys(&entries),
+            vec![("Smith2020".to_string(), "smith2020".to_string())]
+        );
+        let formatter = Formatter::builder().key_case(KeyCase::Preserve).build();
+        assert_eq!(
+            formatter.format_entries(&entries),
+            "@misc{adams2019}\n\n@misc{Smith2020}"
+        );
+        assert!(formatter.changed_keys(&entries).is_empty());
+    }
+
+    #[test]
+    fn test_format_ref_entry_indent_and_alignment() {
+        let entry = RefEntry::new(
+            "misc".to_string(),
+            "a".to_string(),
+            vec![
+                Tag::new("title".to_string(), Value::Single("T".to_string())),
+                Tag::new("publisher".to_string(), Value::Integer(1)),
+            ],
+        );
+        let formatter = Formatter::builder()
+            .indent(Indent::Tab)
+            .align_values(true)
+            .build();
+        assert_eq!(
+            formatter.format_ref_entry(&entry),
+            "@misc{a,\n\ttitle     = {T},\n\tpublisher = 1,\n}"
+        );
+        let formatter = Formatter::builder().indent(Indent::Spaces(2)).build();
+        assert_eq!(
+            formatter.format_ref_entry(&entry),
+            "@misc{a,\n  title = {T},\n  publisher = 1,\n}"
+        );
+    }
+
+    #[test_case("5\" disk", "5{\"} disk" ; "bare quote")]
+    #[test_case("G\\\"odel", "G{\\\"o}del" ; "umlaut")]
+    #[test_case("G\\\"{o}del", "G{\\\"{o}}del" ; "umlaut with braced letter")]
+    #[test_case("{\"}quoted{\"}", "{\"}quoted{\"}" ; "already protected")]
+    #[test_case("a \\\" b", "a {\\\"} b" ; "stray umlaut")]
+    #[test_case("a\\\"", "a{\\\"}" ; "trailing umlaut")]
+    fn test_escape_quotes(input: &str, expected: &str) {
+        assert_eq!(escape_quotes(input), expected)
+    }
+
+    #[test]
+    fn test_delimiter_style() {
+        let braced = Tag::new("note".to_string(), Value::Single("a \"b\"".to_string()))
+            .with_delimiter(Delimiter::Braces);
+        let quoted = Tag::new("note".to_string(), Value::Single("c".to_string()))
+            .with_delimiter(Delimiter::Quotes);
+        let formatter = Formatter::builder().build();
+        assert_eq!(formatter.format_tag(&braced), "note = {a \"b\"}");
+        assert_eq!(formatter.format_tag(&quoted), "note = {c}");
+        let formatter = Formatter::builder()
+            .delimiters(DelimiterStyle::Quotes)
+            .build();
+        assert_eq!(formatter.format_tag(&braced), "note = \"a {\"}b{\"}\"");
+        let formatter = Formatter::builder()
+            .delimiters(DelimiterStyle::Preserve)
+            .build();
+        assert_eq!(formatter.format_tag(&braced), "note = {a \"b\"}");
+        assert_eq!(formatter.format_tag(&quoted), "note = \"c\"");
+    }
+
+    #[test]
+    fn test_wrap_value() {
+        let line = "    note = {one two {three four} https://example.com/a/long/path five},";
+        assert_eq!(
+            wrap_value(line, 30),
+            "    note = {one two\n            {three four}\n            https://example.com/a/long/path\n            five},"
+        );
+        assert_eq!(wrap_value(line, 200), line);
+        assert_eq!(
+            wrap_value("    note = {one two}", 18),
+            "    note = {one\n            two}"
+        );
+        // The tab counts as 8 columns, so `\tabstract = "a b` is 23 wide.
+        assert_eq!(
+            wrap_value("\tabstract = \"a b c\",", 24),
+            "\tabstract = \"a b\n\t            c\","
+        );
+        assert_eq!(display_width("\tab\t"), 16);
+    }
+
+    #[test]
+    fn test_format_ref_entry_without_trailing_comma() {
+        let entry = RefEntry::new(
+            "misc".to_string(),
+            "a".to_string(),
+            vec![
+                Tag::new("title".to_string(), Value::Single("T".to_string())),
+                Tag::new("year".to_string(), Value::Integer(2020)),
+            ],
+        );
+        let formatter = Formatter::builder().trailing_comma(false).build();
+        assert_eq!(
+            formatter.format_ref_entry(&entry),
+            "@misc{a,\n    title = {T},\n    year = 2020\n}"
+        );
+        let formatter = Formatter::builder()
+            .trailing_comma(false)
+            .max_width(16)
+            .build();
+        assert_eq!(
+            formatter.format_ref_entry(&entry),
+            "@misc{a,\n    title = {T},\n    year = 2020\n}"
+        );
+    }
+
+    #[test]
+    fn test_verbatim_fields_are_not_wrapped() {
+        let entry = RefEntry::new(
+            "misc".to_string(),
+            "a".to_string(),
+            vec![
+                Tag::new(
+                    "note".to_string(),
+                    Value::Single("see the file".to_string()),
+                ),
+                Tag::new(
+                    "file".to_string(),
+                    Value::Single("My Papers/a long name.pdf".to_string()),
+                ),
+            ],
+        );
+        let formatter = Formatter::builder().sort_tags(false).max_width(20).build();
+        assert_eq!(
+            formatter.format_ref_entry(&entry),
+            "@misc{a,\n    note = {see the\n            file},\n    \
+             file = {My Papers/a long name.pdf},\n}"
+        );
+    }
+
+    #[test]
+    fn test_wrapped_values_parse_back_unchanged() -> Result<()> {
+        let text = "@article{a, abstract = {We study {Very Long Groups} of words \
+                    at https://example.com/x and more words than fit on a line}, \
+                    title = \"A {\\\"o} title with several words\"}";
+        let parse = |text: &str| {
+            let tokens = crate::token::Tokenizer::new(text.chars()).tokenize();
+            crate::parse::Parser::new(tokens.into_iter()).parse()
+        };
+        let entries = parse(text)?;
+        let formatter = Formatter::builder().max_width(30).build();
+        let formatted = formatter.format_entries(&entries);
+        assert!(formatted.lines().count() > 6, "{formatted}");
+        assert_eq!(
+            parse(&formatted)?,
+            parse(&Formatter::builder().build().format_entries(&entries))?
+        );
+        Ok(())
+    }
+
+    #[test_case("tab", Some(Indent::Tab) ; "tab")]
+    #[test_case("2", Some(Indent::Spaces(2)) ; "spaces")]
+    #[test_case("wide", None ; "invalid")]
+    fn test_parse_indent(input: &str, expected: Option<Indent>) {
+        assert_eq!(input.parse().ok(), expected);
+    }
+
+    #[test]
+    fn test_format_entries_crossref_cycle_keeps_sorted_order() {
+        let formatter = Formatter::builder().build();
+        let entries = Entries::new(vec![
+            crossref_entry("b", "a"),
+            crossref_entry("a", "b"),
+            crossref_entry("c", "a"),
+        ]);
+        let keys: Vec<String> = formatter
+            .format_entries(&entries)
+            .lines()
+            .filter_map(|line| line.strip_prefix("@misc{"))
+            .map(|line| line.trim_end_matches(',').to_string())
+            .collect();
+        // c precedes its parent a; the cycle then blocks a and b, so the
+        // earliest of them in sorted order breaks it.
+        assert_eq!(keys, vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_format_entries_crossref_chain() {
+        let formatter = Formatter::builder().build();
+        let entries = Entries::new(vec![
+            crossref_entry("a", "m"),
+            ref_entry("b"),
+            ref_entry("m"),
+            crossref_entry("z", "a"),
+        ]);
+        let keys: Vec<String> = formatter
+            .format_entries(&entries)
+            .lines()
+            .filter_map(|line| line.strip_prefix("@misc{"))
+            .map(|line| line.trim_end_matches(['{', '}', ',']).to_string())
+            .collect();
+        // Each parent waits for its child, and unconstrained b keeps its place.
+        assert_eq!(keys, vec!["b", "z", "a", "m"]);
+    }
+
     #[test]
     fn test_format_entries_remove_comments() {
         let formatter = Formatter::builder().remove_comments(true).build();
@@ -543,6 +1418,23 @@ mod tests {
         assert_eq!(formatter.format_entries(&entries), expected);
     }
 
+    #[test]
+    fn test_format_entries_raw_entry_travels_verbatim() {
+        let formatter = Formatter::builder().remove_comments(true).build();
+        let entries = Entries::new(vec![
+            ref_entry("z"),
+            EntryType::RawEntry(RawEntry::new(
+                "@misc{b,\n  TITLE = = x}".to_string(),
+                Span::default(),
+            )),
+            ref_entry("a"),
+        ]);
+        // The raw entry is never removed as a comment and attaches to the entry
+        // that follows it.
+        let expected = "@misc{b,\n  TITLE = = x}\n@misc{a}\n\n@misc{z}";
+        assert_eq!(formatter.format_entries(&entries), expected);
+    }
+
     #[test]
     fn test_formatter_builder() {
         let formatter = Formatter {
@@ -551,6 +1443,20 @@ mod tests {
             skip_empty_tags: false,
             sort_entries: true,
             sort_tags: false,
+            entry_sort: EntrySort::Key,
+            key_collation: KeyCollation::default(),
+            key_case: KeyCase::default(),
+            entry_type_case: Case::Lower,
+            keyword_case: Case::Upper,
+            field_name_case: Case::Lower,
+            indent: Indent::default(),
+            align_values: false,
+            delimiters: None,
+            max_width: None,
+            trailing_comma: true,
+            normalize_pages: false,
+            normalize_names: None,
+            tag_order: TagOrder::default(),
         };
         let formatter_from_builder = FormatterBuilder::new()
             .skip_empty_tags(false)
@@ -559,6 +1465,61 @@ mod tests {
         assert_eq!(formatter, formatter_from_builder)
     }
 
+    #[test]
+    fn test_format_tag_normalize_pages() {
+        let tag = Tag::new("Pages".to_string(), Value::Single("pp. 1-10".to_string()));
+        let formatter = Formatter::builder().build();
+        assert_eq!(formatter.format_tag(&tag), "pages = {pp. 1-10}");
+        let formatter = Formatter::builder().normalize_pages(true).build();
+        assert_eq!(formatter.format_tag(&tag), "pages = {1--10}");
+    }
+
+    #[test]
+    fn test_format_tag_normalize_names() {
+        let tag = Tag::new(
+            "Author".to_string(),
+            Value::Single("J. Gregory Pauloski and {IEEE Computer Society}".to_string()),
+        );
+        let formatter = Formatter::builder().build();
+        assert_eq!(
+            formatter.format_tag(&tag),
+            "author = {J. Gregory Pauloski and {IEEE Computer Society}}"
+        );
+        let formatter = Formatter::builder()
+            .normalize_names(NameStyle::Initials)
+            .build();
+        assert_eq!(
+            formatter.format_tag(&tag),
+            "author = {Pauloski, J. G. and {IEEE Computer Society}}"
+        );
+    }
+
+    #[test_case("409-418", "409--418" ; "hyphen")]
+    #[test_case("409--418", "409--418" ; "double hyphen")]
+    #[test_case("409---418", "409--418" ; "triple hyphen")]
+    #[test_case("409–418", "409--418" ; "en dash")]
+    #[test_case("409—418", "409--418" ; "em dash")]
+    #[test_case("409 - 418", "409--418" ; "spaced hyphen")]
+    #[test_case("pp. 409-418", "409--418" ; "pp prefix")]
+    #[test_case("PP 12-14", "12--14" ; "uppercase pp prefix")]
+    #[test_case("1-5, 7-9", "1--5, 7--9" ; "several ranges")]
+    #[test_case("e1234", "e1234" ; "article number")]
+    #[test_case("xii", "xii" ; "roman numeral")]
+    #[test_case("xii-xv", "xii--xv" ; "roman numeral range")]
+    #[test_case("123-", "123-" ; "open range")]
+    #[test_case("12-14-16", "12-14-16" ; "not a range")]
+    #[test_case("1,234-1,240", "1,234--1,240" ; "thousands separators")]
+    #[test_case("1,234", "1,234" ; "thousands separator single page")]
+    #[test_case("1-5,7-9", "1--5,7--9" ; "ranges without spaces")]
+    #[test_case("1-5,  7-9", "1--5,  7--9" ; "separator kept")]
+    #[test_case("1,2-3", "1,2-3" ; "ambiguous comma")]
+    #[test_case("pp.", "pp." ; "prefix only")]
+    #[test_case("409−418", "409--418" ; "minus sign")]
+    #[test_case("409‐418", "409--418" ; "unicode hyphen")]
+    fn test_normalize_pages(input: &str, expected: &str) {
+        assert_eq!(normalize_pages(input), expected)
+    }
+
     #[test_case("foo", "foo" ; "default")]
     #[test_case("{foo}", "foo" ; "simple")]
     #[test_case("{foo} {} {bar}}", "foo  bar" ; "braces complex")]
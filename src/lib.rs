@@ -1,6 +1,11 @@
+pub mod case;
+pub mod config;
+pub mod cst;
+pub mod diagnostic;
 pub mod error;
 pub mod format;
 pub mod models;
+pub mod order;
 pub mod parse;
 pub mod token;
 
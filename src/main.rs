@@ -1,9 +1,16 @@
-use bibtex_format::format::Formatter;
+use bibtex_format::case::{Case, KeyCase};
+use bibtex_format::config::{Config, CrossrefMode};
+use bibtex_format::diagnostic::Diagnostic;
+use bibtex_format::format::{DelimiterStyle, Formatter, Indent};
+use bibtex_format::models::{EntryType, MonthStyle, NameStyle};
+use bibtex_format::order::{EntrySort, TagFallback};
 use bibtex_format::parse;
 use bibtex_format::token::Tokenizer;
 
 use clap::Parser;
 use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
 use std::process::ExitCode;
 
 /// Parse and format bibtex files.
@@ -24,28 +31,228 @@ struct Args {
     #[arg(long)]
     check: bool,
     /// Skip sorting entries.
-    #[arg(long)]
+    #[arg(long, overrides_with = "sort_entries")]
     skip_sort_entries: bool,
+    /// Sort entries (the default).
+    #[arg(long, overrides_with = "skip_sort_entries")]
+    sort_entries: bool,
+    /// How to sort entries. Preambles and @string definitions always come
+    /// first.
+    #[arg(long, value_enum, value_name = "ORDER")]
+    sort_entries_by: Option<EntrySort>,
+    /// Compare numbers in cite keys by value, so `paper9` sorts before
+    /// `paper10`.
+    #[arg(long, overrides_with = "no_natural_key_sort")]
+    natural_key_sort: bool,
+    /// Compare cite keys as plain text (the default).
+    #[arg(long, overrides_with = "natural_key_sort")]
+    no_natural_key_sort: bool,
+    /// Ignore case and accents in cite keys when sorting.
+    #[arg(long, overrides_with = "no_unicode_key_sort")]
+    unicode_key_sort: bool,
+    /// Compare cite keys by case and accents (the default).
+    #[arg(long, overrides_with = "unicode_key_sort")]
+    no_unicode_key_sort: bool,
+    /// How to case cite keys. When lowercasing, each changed key is reported
+    /// so citations can be updated.
+    #[arg(long, value_enum, value_name = "CASE")]
+    key_case: Option<KeyCase>,
+    /// How to case entry types, e.g. `@article` (default: lower).
+    #[arg(long, value_enum, value_name = "CASE")]
+    entry_type_case: Option<Case>,
+    /// How to case the @string, @preamble, and @comment keywords (default:
+    /// upper).
+    #[arg(long, value_enum, value_name = "CASE")]
+    keyword_case: Option<Case>,
+    /// How to case field names (default: lower).
+    #[arg(long, value_enum, value_name = "CASE")]
+    field_name_case: Option<Case>,
+    /// Indent tags by this many spaces, or `tab` (default: 4).
+    #[arg(long, value_name = "WIDTH|tab")]
+    indent: Option<Indent>,
+    /// Pad tag names so the `=` signs in each entry line up.
+    #[arg(long, overrides_with = "no_align_values")]
+    align_values: bool,
+    /// Leave a single space before each `=` (the default).
+    #[arg(long, overrides_with = "align_values")]
+    no_align_values: bool,
+    /// Delimiters around string values (default: braces for tags, quotes for
+    /// @string definitions).
+    #[arg(long, value_enum, value_name = "STYLE")]
+    delimiters: Option<DelimiterStyle>,
+    /// Wrap long values at spaces to keep lines within this many characters.
+    #[arg(long, value_name = "WIDTH")]
+    max_width: Option<usize>,
+    /// Omit the comma after the last tag in each entry.
+    #[arg(long, overrides_with = "trailing_comma")]
+    no_trailing_comma: bool,
+    /// Write a comma after the last tag in each entry (the default).
+    #[arg(long, overrides_with = "no_trailing_comma")]
+    trailing_comma: bool,
     /// Skip sorting tags.
-    #[arg(long)]
+    #[arg(long, overrides_with = "sort_tags")]
     skip_sort_tags: bool,
+    /// Sort tags (the default).
+    #[arg(long, overrides_with = "skip_sort_tags")]
+    sort_tags: bool,
+    /// Comma-separated tag names to put first in every entry, in order
+    /// (default: title,author).
+    #[arg(long, value_name = "FIELDS", value_delimiter = ',')]
+    tag_order: Option<Vec<String>>,
+    /// Tag order for one entry type, e.g. `article=author,title,journal`.
+    /// Overrides --tag-order for that type; may be repeated.
+    #[arg(long, value_name = "TYPE=FIELDS", value_parser = parse_type_tag_order)]
+    type_tag_order: Vec<(String, Vec<String>)>,
+    /// How to order tags not named in the tag order.
+    #[arg(long, value_enum, value_name = "ORDER")]
+    tag_order_fallback: Option<TagFallback>,
     /// Skip formatting titles.
-    #[arg(long)]
+    #[arg(long, overrides_with = "format_title")]
     skip_title_format: bool,
+    /// Format titles (the default).
+    #[arg(long, overrides_with = "skip_title_format")]
+    format_title: bool,
     /// Remove tags with empty contents.
-    #[arg(long)]
+    #[arg(long, overrides_with = "keep_empty_tags")]
     remove_empty_tags: bool,
+    /// Keep tags with empty contents (the default).
+    #[arg(long, overrides_with = "remove_empty_tags")]
+    keep_empty_tags: bool,
     /// Remove all comments (implicit text and @comment entries).
-    #[arg(long)]
+    #[arg(long, overrides_with = "keep_comments")]
     remove_comments: bool,
+    /// Keep comments (the default).
+    #[arg(long, overrides_with = "remove_comments")]
+    keep_comments: bool,
     /// Collapse exact-duplicate entries (same key and content). Entries that
     /// share a key but differ are all kept and reported as warnings.
-    #[arg(long)]
+    #[arg(long, overrides_with = "keep_duplicates")]
     remove_duplicates: bool,
+    /// Keep duplicate entries (the default).
+    #[arg(long, overrides_with = "remove_duplicates")]
+    keep_duplicates: bool,
+    /// Replace @string macro references, month macros, and `#` concatenations
+    /// with the text they expand to. The @string definitions are kept.
+    #[arg(long, overrides_with = "no_expand_strings")]
+    expand_strings: bool,
+    /// Keep @string macro references (the default).
+    #[arg(long, overrides_with = "expand_strings")]
+    no_expand_strings: bool,
+    /// Define @string macros for venue-like field values (journal, booktitle,
+    /// publisher, ...) repeated in at least MIN entries (minimum 2) and
+    /// reference them instead. Existing @string definitions are reused
+    /// wherever their text matches.
+    #[arg(long, value_name = "MIN", conflicts_with = "expand_strings")]
+    factor_strings: Option<usize>,
+    /// Handle crossref/xdata inheritance: copy inherited fields into each
+    /// entry, or verify that every parent exists (and, when entries are not
+    /// sorted, follows its children).
+    #[arg(long, value_enum, value_name = "MODE")]
+    crossref: Option<CrossrefMode>,
+    /// Rewrite recognizable month fields (e.g. `{March}`, `"mar"`, `{3}`) as
+    /// month macros or integers. Unrecognized months are left as they are and
+    /// reported as warnings.
+    #[arg(long, value_enum, value_name = "STYLE")]
+    normalize_months: Option<MonthStyle>,
+    /// Strip resolver prefixes and percent-escapes from DOIs, and escape `_`
+    /// and `%` in URLs outside verbatim fields such as `url`.
+    #[arg(long, overrides_with = "no_normalize_links")]
+    normalize_links: bool,
+    /// Leave DOIs and URLs as written (the default).
+    #[arg(long, overrides_with = "normalize_links")]
+    no_normalize_links: bool,
+    /// Replace a `url` that points at a DOI resolver with a `doi` field, or
+    /// remove it if it repeats the entry's `doi`. Applies only when links are
+    /// normalized, whether by --normalize-links or a configuration file.
+    #[arg(long, overrides_with = "no_move_doi_urls")]
+    move_doi_urls: bool,
+    /// Keep DOI `url` fields as written (the default).
+    #[arg(long, overrides_with = "move_doi_urls")]
+    no_move_doi_urls: bool,
+    /// Write page ranges with `--`, e.g. `pp. 409-418` becomes `409--418`.
+    #[arg(long, overrides_with = "no_normalize_pages")]
+    normalize_pages: bool,
+    /// Leave page ranges as written (the default).
+    #[arg(long, overrides_with = "normalize_pages")]
+    no_normalize_pages: bool,
+    /// Rewrite author and editor lists as `Last, First` names, optionally
+    /// with given names abbreviated to initials. Brace-protected names such
+    /// as `{IEEE Computer Society}` are kept as they are.
+    #[arg(long, value_enum, value_name = "STYLE")]
+    normalize_names: Option<NameStyle>,
+    /// Read settings from this configuration file instead of discovering a
+    /// .bibtex-format.toml from each input. Flags take precedence over it.
+    #[arg(long, value_name = "PATH")]
+    config: Option<String>,
+}
+
+impl Args {
+    /// The settings given as flags. Flags that were not passed are left unset
+    /// so that a configuration file can supply them; each on/off setting has a
+    /// flag for either value so a configuration file can be overridden both
+    /// ways.
+    fn overrides(&self) -> Config {
+        Config {
+            format_title: flag(self.format_title, self.skip_title_format),
+            remove_comments: flag(self.remove_comments, self.keep_comments),
+            remove_empty_tags: flag(self.remove_empty_tags, self.keep_empty_tags),
+            sort_entries: flag(self.sort_entries, self.skip_sort_entries),
+            sort_entries_by: self.sort_entries_by,
+            natural_key_sort: flag(self.natural_key_sort, self.no_natural_key_sort),
+            unicode_key_sort: flag(self.unicode_key_sort, self.no_unicode_key_sort),
+            key_case: self.key_case,
+            entry_type_case: self.entry_type_case,
+            keyword_case: self.keyword_case,
+            field_name_case: self.field_name_case,
+            indent: self.indent,
+            align_values: flag(self.align_values, self.no_align_values),
+            delimiters: self.delimiters,
+            max_width: self.max_width,
+            trailing_comma: flag(self.trailing_comma, self.no_trailing_comma),
+            normalize_pages: flag(self.normalize_pages, self.no_normalize_pages),
+            normalize_names: self.normalize_names,
+            sort_tags: flag(self.sort_tags, self.skip_sort_tags),
+            tag_order: self.tag_order.clone(),
+            tag_order_by_type: (!self.type_tag_order.is_empty())
+                .then(|| self.type_tag_order.iter().cloned().collect()),
+            tag_order_fallback: self.tag_order_fallback,
+            remove_duplicates: flag(self.remove_duplicates, self.keep_duplicates),
+            expand_strings: flag(self.expand_strings, self.no_expand_strings),
+            factor_strings: self.factor_strings,
+            crossref: self.crossref,
+            normalize_months: self.normalize_months,
+            normalize_links: flag(self.normalize_links, self.no_normalize_links),
+            move_doi_urls: flag(self.move_doi_urls, self.no_move_doi_urls),
+        }
+    }
+}
+
+/// The setting given by a pair of opposing flags, if either was passed. At
+/// most one is set, as each overrides the other.
+const fn flag(on: bool, off: bool) -> Option<bool> {
+    if on {
+        Some(true)
+    } else if off {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn parse_type_tag_order(value: &str) -> Result<(String, Vec<String>), String> {
+    let (kind, fields) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected TYPE=FIELDS, found `{value}`"))?;
+    let fields = fields
+        .split(',')
+        .map(|field| field.trim().to_string())
+        .collect();
+    Ok((kind.trim().to_lowercase(), fields))
 }
 
 /// Exit codes. pre-commit (and any caller) treats any non-zero code as failure.
-/// Code 2 matches clap's own exit code for invalid arguments.
+/// Code 2 matches clap's own exit code for invalid arguments, and is also used
+/// for invalid configuration files.
 const EXIT_READ_ERROR: u8 = 1;
 const EXIT_ARG_ERROR: u8 = 2;
 const EXIT_PARSE_ERROR: u8 = 3;
@@ -60,20 +267,49 @@ fn main() -> ExitCode {
         return ExitCode::from(EXIT_ARG_ERROR);
     }
 
-    let formatter = Formatter::builder()
-        .format_title(!args.skip_title_format)
-        .remove_comments(args.remove_comments)
-        .skip_empty_tags(args.remove_empty_tags)
-        .sort_entries(!args.skip_sort_entries)
-        .sort_tags(!args.skip_sort_tags)
-        .build();
+    let explicit = match args
+        .config
+        .as_deref()
+        .map(|path| Config::load(Path::new(path)))
+    {
+        Some(Ok(config)) => Some(config),
+        Some(Err(error)) => {
+            eprintln!("Error loading config file: {error}");
+            return ExitCode::from(EXIT_ARG_ERROR);
+        }
+        None => None,
+    };
 
     let mut reformatted = false;
+    let mut invalid = false;
     for input in &args.inputs {
-        let formatted = match format_file(input, &formatter, args.remove_duplicates) {
+        let config = match &explicit {
+            Some(config) => config.clone(),
+            None => match Config::discover(Path::new(input)).map(|path| Config::load(&path)) {
+                Some(Ok(config)) => config,
+                Some(Err(error)) => {
+                    eprintln!("Error loading config file: {error}");
+                    return ExitCode::from(EXIT_ARG_ERROR);
+                }
+                None => Config::default(),
+            },
+        };
+        let settings = args.overrides().or(config);
+        if let Err(error) = settings.validate() {
+            eprintln!("Error: {error}");
+            return ExitCode::from(EXIT_ARG_ERROR);
+        }
+        // Unlike the library, the command line keeps empty tags by default.
+        let formatter = settings
+            .apply(Formatter::builder().skip_empty_tags(false))
+            .build();
+
+        let formatted = match format_file(input, &formatter, &settings) {
             Ok(formatted) => formatted,
             Err(code) => return ExitCode::from(code),
         };
+        invalid |= formatted.invalid;
+        let formatted = formatted.contents;
 
         if args.write || args.check {
             // In write/check mode files carry a single trailing newline so the
@@ -110,17 +346,33 @@ fn main() -> ExitCode {
         }
     }
 
-    if reformatted {
+    // Malformed entries are kept verbatim and the rest of the file is still
+    // formatted, but the run fails so the broken entries are not overlooked.
+    // The same goes for failed crossref and cite key verification.
+    if invalid {
+        ExitCode::from(EXIT_PARSE_ERROR)
+    } else if reformatted {
         ExitCode::from(EXIT_REFORMATTED)
     } else {
         ExitCode::SUCCESS
     }
 }
 
+/// The formatted contents of a single file.
+struct Formatted {
+    contents: String,
+    /// Whether the file contained malformed entries, which are kept verbatim,
+    /// or failed crossref or cite key verification.
+    invalid: bool,
+}
+
 /// Read, parse, and format a single file, returning its formatted contents with
 /// a single trailing newline. On failure a message is printed and the matching
 /// exit code is returned in `Err`.
-fn format_file(input: &str, formatter: &Formatter, remove_duplicates: bool) -> Result<String, u8> {
+fn format_file(input: &str, formatter: &Formatter, settings: &Config) -> Result<Formatted, u8> {
+    // Color diagnostics only when a person is likely reading them.
+    let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+
     let raw_bibtex = match fs::read_to_string(input) {
         Ok(raw) => raw,
         Err(error) => {
@@ -132,20 +384,81 @@ fn format_file(input: &str, formatter: &Formatter, remove_duplicates: bool) -> R
     let mut tokenizer = Tokenizer::new(raw_bibtex.chars());
     let tokens = tokenizer.tokenize();
 
+    // Recover from malformed entries so every problem in the file is reported
+    // at once rather than only the first, and so the rest of the file can still
+    // be formatted around them.
     let mut parser = parse::Parser::new(tokens.into_iter());
-    let mut entries = match parser.parse() {
-        Ok(entries) => entries,
-        Err(error) => {
-            eprintln!("{error}");
-            return Err(EXIT_PARSE_ERROR);
+    let (mut entries, errors) = parser.parse_recovering();
+    for error in &errors {
+        let diagnostic = Diagnostic::from(error);
+        eprintln!("{}\n", diagnostic.render(input, &raw_bibtex, color));
+    }
+    for entry in entries.iter() {
+        if let EntryType::RawEntry(raw) = entry {
+            let diagnostic =
+                Diagnostic::warning("malformed entry was kept verbatim").with_span(raw.span());
+            eprintln!("{}\n", diagnostic.render(input, &raw_bibtex, color));
         }
-    };
+    }
 
-    if remove_duplicates {
+    if settings.remove_duplicates == Some(true) {
         for warning in entries.remove_duplicates() {
             eprintln!("{warning}");
         }
     }
 
-    Ok(format!("{}\n", formatter.format_entries(&entries)))
+    if let Some(style) = settings.normalize_months {
+        for warning in entries.normalize_months(style) {
+            eprintln!("{warning}");
+        }
+    }
+
+    if settings.normalize_links == Some(true) {
+        for warning in entries.normalize_links(settings.move_doi_urls == Some(true)) {
+            eprintln!("{warning}");
+        }
+    }
+
+    if settings.expand_strings == Some(true) {
+        if let Err(error) = entries.resolve_strings() {
+            let diagnostic = Diagnostic::from(&error);
+            eprintln!("{}\n", diagnostic.render(input, &raw_bibtex, color));
+            return Err(EXIT_PARSE_ERROR);
+        }
+    }
+
+    if let Some(min_count) = settings.factor_strings {
+        entries.factor_strings(min_count);
+    }
+
+    let mut check_errors = Vec::new();
+    match settings.crossref {
+        Some(CrossrefMode::Inline) => {
+            if let Err(error) = entries.resolve_crossrefs() {
+                let diagnostic = Diagnostic::from(&error);
+                eprintln!("{}\n", diagnostic.render(input, &raw_bibtex, color));
+                return Err(EXIT_PARSE_ERROR);
+            }
+        }
+        Some(CrossrefMode::Verify) => {
+            check_errors = entries.check_crossrefs(settings.sort_entries == Some(false));
+        }
+        None => (),
+    }
+    if settings.key_case == Some(KeyCase::Strict) {
+        check_errors.extend(entries.check_key_case());
+    }
+    for error in &check_errors {
+        let diagnostic = Diagnostic::from(error);
+        eprintln!("{}\n", diagnostic.render(input, &raw_bibtex, color));
+    }
+
+    for (original, key) in formatter.changed_keys(&entries) {
+        eprintln!("{input}: cite key `{original}` is written as `{key}`");
+    }
+
+    Ok(Formatted {
+        contents: format!("{}\n", formatter.format_entries(&entries)),
+        invalid: !errors.is_empty() || !check_errors.is_empty(),
+    })
 }
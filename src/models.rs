@@ -1,7 +1,17 @@
+mod crossref;
 mod entry;
+mod links;
+mod macros;
+mod month;
+mod names;
 mod tag;
 
 pub use crate::models::entry::{
-    CommentEntry, CommentKind, Entries, Entry, EntryType, PreambleEntry, RefEntry, StringEntry,
+    CommentEntry, CommentKind, Entries, Entry, EntryType, PreambleEntry, RawEntry, RefEntry,
+    StringEntry,
 };
-pub use crate::models::tag::{Part, Sequence, Tag, Value};
+pub use crate::models::links::is_verbatim;
+pub use crate::models::macros::Macros;
+pub use crate::models::month::MonthStyle;
+pub use crate::models::names::{NameStyle, Person};
+pub use crate::models::tag::{Delimiter, Part, Sequence, Tag, Value};
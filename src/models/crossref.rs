@@ -0,0 +1,148 @@
+use crate::models::{Part, Tag, Value};
+
+/// Fields that identify or configure an entry itself and so are never
+/// inherited from a parent.
+const NOT_INHERITED: [&str; 22] = [
+    "crossref",
+    "entryset",
+    "entrysubtype",
+    "execute",
+    "ids",
+    "indexsorttitle",
+    "indextitle",
+    "key",
+    "label",
+    "options",
+    "presort",
+    "related",
+    "relatedoptions",
+    "relatedstring",
+    "relatedtype",
+    "shorthand",
+    "shorthandintro",
+    "shorttitle",
+    "sortkey",
+    "sorttitle",
+    "xdata",
+    "xref",
+];
+
+const MULTI_VOLUME: [&str; 4] = ["mvbook", "mvcollection", "mvproceedings", "mvreference"];
+const VOLUMES: [&str; 11] = [
+    "book",
+    "bookinbook",
+    "collection",
+    "inbook",
+    "incollection",
+    "inproceedings",
+    "inreference",
+    "proceedings",
+    "reference",
+    "suppbook",
+    "suppcollection",
+];
+const CONTAINERS: [&str; 4] = ["book", "collection", "proceedings", "reference"];
+const PARTS: [&str; 7] = [
+    "bookinbook",
+    "inbook",
+    "incollection",
+    "inproceedings",
+    "inreference",
+    "suppbook",
+    "suppcollection",
+];
+
+/// The child fields a parent's field fills in under biblatex's default
+/// crossref inheritance rules, e.g. a proceedings' `title` becomes the
+/// `booktitle` of its papers. Fields with no special rule keep their name;
+/// an empty result means the field is not inherited.
+pub fn inherited_names(parent_kind: &str, child_kind: &str, field: &str) -> Vec<String> {
+    let parent_kind = parent_kind.to_lowercase();
+    let child_kind = child_kind.to_lowercase();
+    let field = field.to_lowercase();
+    let (parent, child) = (parent_kind.as_str(), child_kind.as_str());
+
+    if NOT_INHERITED.contains(&field.as_str()) {
+        return Vec::new();
+    }
+
+    let title_prefix = if MULTI_VOLUME.contains(&parent) && VOLUMES.contains(&child) {
+        Some("main")
+    } else if CONTAINERS.contains(&parent) && PARTS.contains(&child) {
+        Some("book")
+    } else if parent == "periodical" && matches!(child, "article" | "suppperiodical") {
+        Some("journal")
+    } else {
+        None
+    };
+    if let Some(prefix) = title_prefix {
+        if matches!(field.as_str(), "title" | "subtitle" | "titleaddon") {
+            return vec![format!("{prefix}{field}")];
+        }
+    }
+
+    if field == "author"
+        && matches!(parent, "book" | "mvbook")
+        && matches!(child, "inbook" | "bookinbook" | "suppbook")
+    {
+        return vec!["author".to_string(), "bookauthor".to_string()];
+    }
+
+    vec![field]
+}
+
+/// The cite keys a `crossref` or `xdata` tag refers to. `xdata` may list
+/// several keys separated by commas.
+pub fn parent_keys(tag: &Tag) -> Vec<String> {
+    let text = match &tag.value {
+        Value::Single(s) => s.clone(),
+        Value::Integer(n) => n.to_string(),
+        Value::Sequence(seq) => match seq.parts().as_slice() {
+            [Part::Quoted(s) | Part::Value(s)] => s.clone(),
+            _ => return Vec::new(),
+        },
+    };
+    text.split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inherited_names() {
+        assert_eq!(
+            inherited_names("proceedings", "inproceedings", "title"),
+            vec!["booktitle"]
+        );
+        assert_eq!(
+            inherited_names("MVBook", "book", "Subtitle"),
+            vec!["mainsubtitle"]
+        );
+        assert_eq!(
+            inherited_names("book", "inbook", "author"),
+            vec!["author", "bookauthor"]
+        );
+        assert_eq!(
+            inherited_names("periodical", "article", "title"),
+            vec!["journaltitle"]
+        );
+        assert_eq!(
+            inherited_names("proceedings", "inproceedings", "year"),
+            vec!["year"]
+        );
+        assert_eq!(inherited_names("misc", "misc", "title"), vec!["title"]);
+        assert!(inherited_names("proceedings", "inproceedings", "shorttitle").is_empty());
+        assert!(inherited_names("book", "inbook", "crossref").is_empty());
+    }
+
+    #[test]
+    fn test_parent_keys() {
+        let tag = Tag::new("xdata".to_string(), Value::Single("a, b".to_string()));
+        assert_eq!(parent_keys(&tag), vec!["a", "b"]);
+    }
+}
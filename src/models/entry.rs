@@ -1,5 +1,11 @@
-use crate::models::{Sequence, Tag};
+use crate::models::crossref::{inherited_names, parent_keys};
+use crate::models::links::{doi_from_url, escape_urls, is_verbatim, normalize_doi, unescape};
+use crate::models::macros::generate_name;
+use crate::models::{Macros, MonthStyle, Part, Person, Sequence, Tag, Value};
+use crate::token::{Position, Span};
+use crate::{Error, Result};
 use std::cmp::{Ord, Ordering, PartialOrd};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Debug;
 
 pub trait Entry: Debug + Ord + PartialOrd {}
@@ -10,6 +16,20 @@ pub enum EntryType {
     StringEntry(StringEntry),
     CommentEntry(CommentEntry),
     RefEntry(RefEntry),
+    RawEntry(RawEntry),
+}
+
+impl EntryType {
+    /// The source range the entry was parsed from.
+    pub const fn span(&self) -> Span {
+        match self {
+            Self::PreambleEntry(e) => e.span(),
+            Self::StringEntry(e) => e.span(),
+            Self::CommentEntry(e) => e.span(),
+            Self::RefEntry(e) => e.span,
+            Self::RawEntry(e) => e.span(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -58,6 +78,501 @@ impl Entries {
         self.entries.sort();
     }
 
+    /// Rewrite every recognizable `month` field in `style`, e.g. `{March}`,
+    /// `"mar"`, and `{3}` all become `mar`. Unrecognized values are left as
+    /// they are and reported in the returned warnings.
+    pub fn normalize_months(&mut self, style: MonthStyle) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for entry in &mut self.entries {
+            let EntryType::RefEntry(e) = entry else {
+                continue;
+            };
+            for tag in &mut e.tags {
+                if !tag.name.eq_ignore_ascii_case("month") {
+                    continue;
+                }
+                match style.normalize(&tag.value) {
+                    Some(value) => tag.value = value,
+                    None => {
+                        let text = tag.value.text();
+                        let location = if tag.value_span.start.line > 0 {
+                            format!(" (at {})", tag.value_span.start)
+                        } else {
+                            String::new()
+                        };
+                        warnings.push(format!(
+                            "warning: month '{text}' of '{}' is not recognized{location}; \
+                             leaving it unchanged",
+                            e.key
+                        ));
+                    }
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Normalize DOIs and URLs in reference entries. `doi` fields lose resolver
+    /// prefixes such as `https://doi.org/` and percent-escapes, verbatim fields
+    /// such as `url` lose LaTeX escapes of `_` and `%`, and URLs in other fields
+    /// gain them. Nothing case-sensitive is recased.
+    ///
+    /// With `move_doi_urls`, a `url` that points at a DOI resolver becomes the
+    /// entry's `doi`, or is removed if it repeats the `doi`. Returns a warning
+    /// for each entry whose `url` and `doi` name different DOIs.
+    pub fn normalize_links(&mut self, move_doi_urls: bool) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for entry in &mut self.entries {
+            let EntryType::RefEntry(e) = entry else {
+                continue;
+            };
+            for tag in &mut e.tags {
+                let Value::Single(text) = &tag.value else {
+                    continue;
+                };
+                let text = if tag.name.eq_ignore_ascii_case("doi") {
+                    normalize_doi(text)
+                } else if is_verbatim(&tag.name) {
+                    unescape(text)
+                } else {
+                    escape_urls(text)
+                };
+                tag.value = Value::Single(text);
+            }
+            if !move_doi_urls {
+                continue;
+            }
+            let Some((index, url_doi)) = e.tags.iter().enumerate().find_map(|(i, tag)| match &tag
+                .value
+            {
+                Value::Single(url) if tag.name.eq_ignore_ascii_case("url") => {
+                    doi_from_url(url).map(|doi| (i, doi))
+                }
+                _ => None,
+            }) else {
+                continue;
+            };
+            let doi = e
+                .tags
+                .iter()
+                .find(|tag| tag.name.eq_ignore_ascii_case("doi"))
+                .map(|tag| tag.value.clone());
+            match doi {
+                None => {
+                    let tag = &mut e.tags[index];
+                    tag.name = "doi".to_string();
+                    tag.value = Value::Single(url_doi);
+                }
+                // DOIs are case-insensitive, so `10.1109/ABC` repeats `10.1109/abc`.
+                Some(Value::Single(doi)) if doi.eq_ignore_ascii_case(&url_doi) => {
+                    e.tags.remove(index);
+                }
+                Some(Value::Single(_)) => warnings.push(format!(
+                    "warning: url of '{}' points at DOI '{url_doi}', which is not its doi; \
+                     keeping both",
+                    e.key
+                )),
+                // A macro or concatenation cannot be compared, so keep both.
+                Some(_) => (),
+            }
+        }
+        warnings
+    }
+
+    /// Replace `@string` macro references and `#` concatenations in reference
+    /// entries and preambles with the plain text BibTeX would see (see
+    /// [`Macros`]). The `@string` definitions themselves are kept so other
+    /// files sharing them are unaffected. Fails without modifying anything if
+    /// a macro is undefined or macros refer to each other in a cycle.
+    pub fn resolve_strings(&mut self) -> Result<()> {
+        let macros = Macros::from_entries(self)?;
+
+        let mut resolved: Vec<(usize, Vec<Value>)> = Vec::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            let values = match entry {
+                EntryType::RefEntry(e) => e
+                    .tags
+                    .iter()
+                    .map(|tag| match &tag.value {
+                        Value::Sequence(_) => {
+                            macros.expand(&tag.value, tag.value_span).map(Value::Single)
+                        }
+                        Value::Single(s) => Ok(Value::Single(s.clone())),
+                        Value::Integer(n) => Ok(Value::Integer(*n)),
+                    })
+                    .collect::<Result<_>>()?,
+                EntryType::PreambleEntry(e) => {
+                    let text = macros.expand_sequence(e.body(), e.span())?;
+                    vec![Value::Sequence(Sequence::new(vec![Part::Quoted(text)]))]
+                }
+                _ => continue,
+            };
+            resolved.push((i, values));
+        }
+
+        for (i, values) in resolved {
+            match &mut self.entries[i] {
+                EntryType::RefEntry(e) => {
+                    for (tag, value) in e.tags.iter_mut().zip(values) {
+                        tag.value = value;
+                    }
+                }
+                EntryType::PreambleEntry(e) => {
+                    if let Some(Value::Sequence(body)) = values.into_iter().next() {
+                        e.body = body;
+                    }
+                }
+                _ => unreachable!("only reference entries and preambles are resolved"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace field values that occur in at least `min_count` reference
+    /// entries (and never fewer than two) with references to new `@string`
+    /// macros, returning the names of the macros defined. Values that already
+    /// match an existing `@string` definition reuse it however often they
+    /// occur. New definitions are inserted above the first reference entry,
+    /// with names generated from the text they abbreviate.
+    ///
+    /// Only braced or quoted values of venue-like fields (`journal`,
+    /// `booktitle`, `publisher`, `institution`, `organization`, `school`,
+    /// `series`, and `address`) are factored; name lists, titles, and verbatim
+    /// fields such as `doi` are left in place so that other normalizations
+    /// still apply to them. A value repeated within one entry counts once.
+    pub fn factor_strings(&mut self, min_count: usize) -> Vec<String> {
+        let mut names: BTreeMap<String, String> = BTreeMap::new();
+        let mut taken: BTreeSet<String> = BTreeSet::new();
+        for entry in &self.entries {
+            if let EntryType::StringEntry(e) = entry {
+                let name = e.tag().name.to_lowercase();
+                if let Value::Single(text) = &e.tag().value {
+                    names.entry(text.clone()).or_insert_with(|| name.clone());
+                }
+                taken.insert(name);
+            }
+        }
+
+        // Keep first-seen order so generated names are deterministic.
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut order: Vec<&str> = Vec::new();
+        for entry in &self.entries {
+            let EntryType::RefEntry(e) = entry else {
+                continue;
+            };
+            let mut seen: HashSet<&str> = HashSet::new();
+            for text in e.tags.iter().filter_map(factorable) {
+                if !seen.insert(text) {
+                    continue;
+                }
+                let count = counts.entry(text).or_insert(0);
+                if *count == 0 {
+                    order.push(text);
+                }
+                *count += 1;
+            }
+        }
+
+        let mut created = Vec::new();
+        for text in order {
+            if counts[text] < min_count.max(2) || names.contains_key(text) {
+                continue;
+            }
+            let text = text.to_string();
+            let name = generate_name(&text, &taken);
+            taken.insert(name.clone());
+            names.insert(text.clone(), name.clone());
+            created.push((name, text));
+        }
+
+        for entry in &mut self.entries {
+            let EntryType::RefEntry(e) = entry else {
+                continue;
+            };
+            for tag in &mut e.tags {
+                if let Some(name) = factorable(tag).and_then(|text| names.get(text)) {
+                    tag.value = Value::Sequence(Sequence::new(vec![Part::Value(name.clone())]));
+                }
+            }
+        }
+
+        if created.is_empty() {
+            return Vec::new();
+        }
+
+        // Insert above the comments attached to the first reference entry so
+        // they stay with it.
+        let mut at = self
+            .entries
+            .iter()
+            .position(|e| matches!(e, EntryType::RefEntry(_)))
+            .unwrap_or(self.entries.len());
+        while at > 0
+            && matches!(
+                self.entries[at - 1],
+                EntryType::CommentEntry(_) | EntryType::RawEntry(_)
+            )
+        {
+            at -= 1;
+        }
+        let leading = self
+            .leading
+            .get(at)
+            .cloned()
+            .unwrap_or_else(|| "\n\n".to_string());
+        if let Some(displaced) = self.leading.get_mut(at) {
+            *displaced = "\n\n".to_string();
+        }
+
+        for (i, (name, text)) in created.iter().enumerate() {
+            let tag = Tag::new(name.clone(), Value::Single(text.clone()));
+            let spacing = if i == 0 {
+                leading.clone()
+            } else {
+                "\n".to_string()
+            };
+            self.entries
+                .insert(at + i, EntryType::StringEntry(StringEntry::new(tag)));
+            self.leading.insert(at + i, spacing);
+        }
+        created.into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Copy the fields each reference entry inherits through `crossref` and
+    /// BibLaTeX `xdata` into the entry itself, then drop those fields, so every
+    /// entry is self-contained. Fields the entry already has are kept. `xdata`
+    /// fields are copied as-is and take precedence over the `crossref` parent,
+    /// whose fields are renamed by the standard inheritance rules (e.g. a
+    /// proceedings' `title` becomes its papers' `booktitle`). Parents are
+    /// resolved recursively and are not modified beyond their own inheritance.
+    /// Fails without modifying anything if a parent is missing or parents form
+    /// a cycle.
+    pub fn resolve_crossrefs(&mut self) -> Result<()> {
+        let index = self.key_index();
+        let mut resolved = BTreeMap::new();
+        for i in index.values() {
+            self.inherited_tags(*i, &index, &mut resolved, &mut Vec::new())?;
+        }
+
+        for (i, tags) in resolved {
+            if let EntryType::RefEntry(e) = &mut self.entries[i] {
+                e.tags = tags.into_iter().filter(|tag| !is_parent_tag(tag)).collect();
+            }
+        }
+        Ok(())
+    }
+
+    /// Report every `crossref` or `xdata` parent that is not defined, every
+    /// cycle of parents, and, with `check_order`, every `crossref` parent that
+    /// appears before one of its children, which BibTeX requires to come
+    /// first. The order only needs checking when entries are written in their
+    /// source order, as sorting already moves parents after their children.
+    pub fn check_crossrefs(&self, check_order: bool) -> Vec<Error> {
+        let index = self.key_index();
+        let mut errors = Vec::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            let EntryType::RefEntry(child) = entry else {
+                continue;
+            };
+            for tag in child.tags.iter().filter(|tag| is_parent_tag(tag)) {
+                for key in parent_keys(tag) {
+                    match index.get(&key.to_lowercase()) {
+                        None => errors.push(Error::UndefinedCrossref(key, tag.value_span)),
+                        Some(&parent)
+                            if check_order
+                                && parent < i
+                                && tag.name.eq_ignore_ascii_case("crossref") =>
+                        {
+                            errors.push(Error::CrossrefOrder(
+                                child.key.clone(),
+                                key,
+                                tag.value_span,
+                            ));
+                        }
+                        Some(_) => (),
+                    }
+                }
+            }
+        }
+
+        // Visit entries in source order so cycles are reported in that order.
+        let mut starts: Vec<usize> = index.values().copied().collect();
+        starts.sort_unstable();
+        let mut visits = vec![Visit::New; self.entries.len()];
+        let mut cycles = Vec::new();
+        for start in starts {
+            if visits[start] == Visit::New {
+                self.find_cycles(start, &index, &mut visits, &mut Vec::new(), &mut cycles);
+            }
+        }
+        errors.extend(
+            cycles.into_iter().map(|cycle| {
+                Error::CrossrefCycle(cycle.iter().map(|&i| self.ref_key(i)).collect())
+            }),
+        );
+        errors
+    }
+
+    /// Report every cite key that differs only in case from an earlier one,
+    /// e.g. `Smith2020` and `smith2020`. Such keys are distinct to biber but
+    /// collide once lowercased. Repeated identical keys are left to
+    /// [`Entries::remove_duplicates`].
+    pub fn check_key_case(&self) -> Vec<Error> {
+        let mut seen: BTreeMap<String, &str> = BTreeMap::new();
+        let mut errors = Vec::new();
+        for entry in &self.entries {
+            let EntryType::RefEntry(e) = entry else {
+                continue;
+            };
+            let first = *seen.entry(e.key.to_lowercase()).or_insert(&e.key);
+            if first != e.key {
+                errors.push(Error::KeyCaseCollision(
+                    e.key.clone(),
+                    first.to_string(),
+                    e.span,
+                ));
+            }
+        }
+        errors
+    }
+
+    /// Reference entry indices by lowercased cite key; the first definition
+    /// of a key wins, as in BibTeX.
+    fn key_index(&self) -> BTreeMap<String, usize> {
+        let mut index = BTreeMap::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            if let EntryType::RefEntry(e) = entry {
+                index.entry(e.key.to_lowercase()).or_insert(i);
+            }
+        }
+        index
+    }
+
+    fn ref_key(&self, i: usize) -> String {
+        match &self.entries[i] {
+            EntryType::RefEntry(e) => e.key.clone(),
+            _ => unreachable!("indices only refer to reference entries"),
+        }
+    }
+
+    /// Indices of the entries that the reference entry at `i` inherits from.
+    fn parents(&self, i: usize, index: &BTreeMap<String, usize>) -> Vec<usize> {
+        let EntryType::RefEntry(e) = &self.entries[i] else {
+            return Vec::new();
+        };
+        e.tags
+            .iter()
+            .filter(|tag| is_parent_tag(tag))
+            .flat_map(parent_keys)
+            .filter_map(|key| index.get(&key.to_lowercase()).copied())
+            .collect()
+    }
+
+    /// Depth-first search from entry `i` through its parents, adding each cycle
+    /// found to `cycles`, starting and ending at its earliest member. Entries
+    /// are marked in `visits` so that each is searched once overall.
+    fn find_cycles(
+        &self,
+        i: usize,
+        index: &BTreeMap<String, usize>,
+        visits: &mut [Visit],
+        path: &mut Vec<usize>,
+        cycles: &mut Vec<Vec<usize>>,
+    ) {
+        visits[i] = Visit::OnPath;
+        path.push(i);
+        for parent in self.parents(i, index) {
+            match visits[parent] {
+                Visit::New => self.find_cycles(parent, index, visits, path, cycles),
+                Visit::OnPath => {
+                    let start = path
+                        .iter()
+                        .position(|&j| j == parent)
+                        .expect("entries on the path are in it");
+                    let mut cycle = path[start..].to_vec();
+                    let first = (0..cycle.len()).min_by_key(|&j| cycle[j]).unwrap_or(0);
+                    cycle.rotate_left(first);
+                    cycle.push(cycle[0]);
+                    cycles.push(cycle);
+                }
+                Visit::Done => (),
+            }
+        }
+        path.pop();
+        visits[i] = Visit::Done;
+    }
+
+    /// The tags of the reference entry at `i` after inheriting from its
+    /// parents, memoized in `resolved`. `resolving` holds the entries whose
+    /// inheritance is in progress, to detect cycles.
+    fn inherited_tags(
+        &self,
+        i: usize,
+        index: &BTreeMap<String, usize>,
+        resolved: &mut BTreeMap<usize, Vec<Tag>>,
+        resolving: &mut Vec<usize>,
+    ) -> Result<Vec<Tag>> {
+        if let Some(tags) = resolved.get(&i) {
+            return Ok(tags.clone());
+        }
+        if let Some(start) = resolving.iter().position(|&j| j == i) {
+            let mut cycle: Vec<String> = resolving[start..]
+                .iter()
+                .map(|&j| self.ref_key(j))
+                .collect();
+            cycle.push(self.ref_key(i));
+            return Err(Error::CrossrefCycle(cycle));
+        }
+        let EntryType::RefEntry(child) = &self.entries[i] else {
+            unreachable!("indices only refer to reference entries");
+        };
+
+        resolving.push(i);
+        let mut tags = child.tags.clone();
+        // xdata before crossref, so xdata wins when both provide a field.
+        let mut parent_tags: Vec<&Tag> = child
+            .tags
+            .iter()
+            .filter(|tag| tag.name.eq_ignore_ascii_case("xdata"))
+            .collect();
+        parent_tags.extend(
+            child
+                .tags
+                .iter()
+                .filter(|tag| tag.name.eq_ignore_ascii_case("crossref")),
+        );
+        for tag in parent_tags {
+            let is_crossref = tag.name.eq_ignore_ascii_case("crossref");
+            for key in parent_keys(tag) {
+                let parent = *index
+                    .get(&key.to_lowercase())
+                    .ok_or_else(|| Error::UndefinedCrossref(key.clone(), tag.value_span))?;
+                let EntryType::RefEntry(parent_entry) = &self.entries[parent] else {
+                    unreachable!("indices only refer to reference entries");
+                };
+                for inherited in self.inherited_tags(parent, index, resolved, resolving)? {
+                    if is_parent_tag(&inherited) {
+                        continue;
+                    }
+                    let names = if is_crossref {
+                        inherited_names(&parent_entry.kind, &child.kind, &inherited.name)
+                    } else {
+                        vec![inherited.name.to_lowercase()]
+                    };
+                    for name in names {
+                        if !tags.iter().any(|t| t.name.eq_ignore_ascii_case(&name)) {
+                            tags.push(Tag::new(name, inherited.value.clone()));
+                        }
+                    }
+                }
+            }
+        }
+        resolving.pop();
+
+        resolved.insert(i, tags.clone());
+        Ok(tags)
+    }
+
     /// Remove exact-duplicate reference entries in place, keeping the first
     /// occurrence of each. Two reference entries are exact duplicates when they
     /// share a cite key and format identically (see [`RefEntry::duplicates`]);
@@ -106,10 +621,23 @@ impl Entries {
             .iter()
             .filter(|(_, group)| group.len() > 1)
             .map(|(key, group)| {
+                // Point at each definition when the entries came from source.
+                let positions: Vec<String> = group
+                    .iter()
+                    .map(|&i| self.entries[i].span())
+                    .filter(|span| span.start.line > 0)
+                    .map(|span| span.start.to_string())
+                    .collect();
+                let location = if positions.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (at {})", positions.join("; "))
+                };
                 format!(
-                    "warning: cite key '{}' has {} conflicting definitions; keeping all",
+                    "warning: cite key '{}' has {} conflicting definitions{}; keeping all",
                     key,
-                    group.len()
+                    group.len(),
+                    location
                 )
             })
             .collect();
@@ -145,16 +673,47 @@ pub enum CommentKind {
     Implicit, // free text between entries
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq)]
 pub struct RefEntry {
     pub kind: String,
     pub key: String,
     pub tags: Vec<Tag>,
+    // Source range from the `@` through the closing delimiter. Default (empty)
+    // for entries not built by the parser. Excluded from equality since it is
+    // source metadata.
+    pub span: Span,
 }
 
 impl RefEntry {
-    pub const fn new(kind: String, key: String, tags: Vec<Tag>) -> Self {
-        Self { kind, key, tags }
+    pub fn new(kind: String, key: String, tags: Vec<Tag>) -> Self {
+        Self {
+            kind,
+            key,
+            tags,
+            span: Span::default(),
+        }
+    }
+
+    pub const fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// The cite key of the entry this one cross-references, if any.
+    pub fn crossref(&self) -> Option<String> {
+        self.tags
+            .iter()
+            .find(|tag| tag.name.eq_ignore_ascii_case("crossref"))
+            .and_then(|tag| parent_keys(tag).into_iter().next())
+    }
+
+    /// The names in the entry's `name` field, e.g. `author` or `editor`, or
+    /// none if the entry has no such field.
+    pub fn persons(&self, name: &str) -> Vec<Person> {
+        self.tags
+            .iter()
+            .find(|tag| tag.name.eq_ignore_ascii_case(name))
+            .map_or_else(Vec::new, Tag::persons)
     }
 
     /// Whether two reference entries are exact duplicates: same kind and cite
@@ -168,6 +727,44 @@ impl RefEntry {
     }
 }
 
+/// The state of an entry in the depth-first search for crossref cycles.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Visit {
+    New,
+    OnPath,
+    Done,
+}
+
+/// Whether a tag names the entries its entry inherits from.
+fn is_parent_tag(tag: &Tag) -> bool {
+    tag.name.eq_ignore_ascii_case("crossref") || tag.name.eq_ignore_ascii_case("xdata")
+}
+
+/// Venue-like fields whose values [`Entries::factor_strings`] may replace with
+/// `@string` macros.
+const FACTORABLE: [&str; 8] = [
+    "address",
+    "booktitle",
+    "institution",
+    "journal",
+    "organization",
+    "publisher",
+    "school",
+    "series",
+];
+
+/// The text of a tag's value if it may be factored into an `@string` macro.
+fn factorable(tag: &Tag) -> Option<&str> {
+    if !FACTORABLE.contains(&tag.name.to_lowercase().as_str()) {
+        return None;
+    }
+    match &tag.value {
+        // Macro definitions are quoted on output, so a `"` cannot be kept.
+        Value::Single(text) if !text.trim().is_empty() && !text.contains('"') => Some(text),
+        _ => None,
+    }
+}
+
 /// Multiset equality for tag lists: the same tags in any order. O(n^2), but tag
 /// counts per entry are small.
 fn same_tags(left: &[Tag], right: &[Tag]) -> bool {
@@ -189,6 +786,12 @@ fn same_tags(left: &[Tag], right: &[Tag]) -> bool {
 
 impl Entry for RefEntry {}
 
+impl PartialEq for RefEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.key == other.key && self.tags == other.tags
+    }
+}
+
 impl PartialOrd for RefEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -201,27 +804,51 @@ impl Ord for RefEntry {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq)]
 pub struct CommentEntry {
     body: String,
     kind: CommentKind,
+    // The `@comment` keyword as written and the source range. Excluded from
+    // equality since they are source metadata.
+    keyword: Option<String>,
+    span: Span,
 }
 
 impl CommentEntry {
-    pub const fn explicit(body: String) -> Self {
+    pub fn explicit(body: String) -> Self {
         Self {
             body,
             kind: CommentKind::Explicit,
+            keyword: None,
+            span: Span::default(),
         }
     }
 
-    pub const fn implicit(body: String) -> Self {
+    pub fn implicit(body: String) -> Self {
         Self {
             body,
             kind: CommentKind::Implicit,
+            keyword: None,
+            span: Span::default(),
         }
     }
 
+    pub const fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Record the entry keyword as written, e.g. `Comment` in `@Comment{...}`.
+    #[must_use]
+    pub fn with_keyword(mut self, keyword: String) -> Self {
+        self.keyword = Some(keyword);
+        self
+    }
+
+    pub fn keyword(&self) -> Option<&str> {
+        self.keyword.as_deref()
+    }
+
     pub fn body(&self) -> &str {
         &self.body
     }
@@ -229,10 +856,20 @@ impl CommentEntry {
     pub const fn kind(&self) -> CommentKind {
         self.kind
     }
+
+    pub const fn span(&self) -> Span {
+        self.span
+    }
 }
 
 impl Entry for CommentEntry {}
 
+impl PartialEq for CommentEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.body == other.body && self.kind == other.kind
+    }
+}
+
 impl PartialOrd for CommentEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -247,21 +884,106 @@ impl Ord for CommentEntry {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub struct PreambleEntry(Sequence);
+/// A malformed entry kept as the exact source text it spanned so that it can
+/// be emitted byte-for-byte while everything around it is formatted.
+#[derive(Debug, Eq)]
+pub struct RawEntry {
+    body: String,
+    // Where the entry appeared in the source, starting at its `@`. Excluded
+    // from equality since it is source metadata.
+    span: Span,
+}
+
+impl RawEntry {
+    pub const fn new(body: String, span: Span) -> Self {
+        Self { body, span }
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub const fn position(&self) -> Position {
+        self.span.start
+    }
+
+    pub const fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Entry for RawEntry {}
+
+impl PartialEq for RawEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.body == other.body
+    }
+}
+
+impl PartialOrd for RawEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RawEntry {
+    fn cmp(&self, _other: &Self) -> Ordering {
+        // Raw entries are opaque; retain their order.
+        Ordering::Equal
+    }
+}
+
+#[derive(Debug, Eq)]
+pub struct PreambleEntry {
+    body: Sequence,
+    // The `@preamble` keyword as written and the source range. Excluded from
+    // equality since they are source metadata.
+    keyword: Option<String>,
+    span: Span,
+}
 
 impl PreambleEntry {
-    pub const fn new(parts: Sequence) -> Self {
-        Self(parts)
+    pub fn new(body: Sequence) -> Self {
+        Self {
+            body,
+            keyword: None,
+            span: Span::default(),
+        }
+    }
+
+    pub const fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Record the entry keyword as written, e.g. `Preamble` in `@Preamble{...}`.
+    #[must_use]
+    pub fn with_keyword(mut self, keyword: String) -> Self {
+        self.keyword = Some(keyword);
+        self
+    }
+
+    pub fn keyword(&self) -> Option<&str> {
+        self.keyword.as_deref()
     }
 
     pub const fn body(&self) -> &Sequence {
-        &self.0
+        &self.body
+    }
+
+    pub const fn span(&self) -> Span {
+        self.span
     }
 }
 
 impl Entry for PreambleEntry {}
 
+impl PartialEq for PreambleEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.body == other.body
+    }
+}
+
 impl PartialOrd for PreambleEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -275,21 +997,57 @@ impl Ord for PreambleEntry {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub struct StringEntry(Tag);
+#[derive(Debug, Eq)]
+pub struct StringEntry {
+    tag: Tag,
+    // The `@string` keyword as written and the source range. Excluded from
+    // equality since they are source metadata.
+    keyword: Option<String>,
+    span: Span,
+}
 
 impl StringEntry {
-    pub const fn new(tag: Tag) -> Self {
-        Self(tag)
+    pub fn new(tag: Tag) -> Self {
+        Self {
+            tag,
+            keyword: None,
+            span: Span::default(),
+        }
+    }
+
+    pub const fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Record the entry keyword as written, e.g. `String` in `@String{...}`.
+    #[must_use]
+    pub fn with_keyword(mut self, keyword: String) -> Self {
+        self.keyword = Some(keyword);
+        self
+    }
+
+    pub fn keyword(&self) -> Option<&str> {
+        self.keyword.as_deref()
     }
 
     pub const fn tag(&self) -> &Tag {
-        &self.0
+        &self.tag
+    }
+
+    pub const fn span(&self) -> Span {
+        self.span
     }
 }
 
 impl Entry for StringEntry {}
 
+impl PartialEq for StringEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.tag == other.tag
+    }
+}
+
 impl PartialOrd for StringEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -298,7 +1056,7 @@ impl PartialOrd for StringEntry {
 
 impl Ord for StringEntry {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.0.cmp(&other.0)
+        self.tag.cmp(&other.tag)
     }
 }
 
@@ -371,6 +1129,29 @@ mod tests {
         assert!(warnings[0].contains('2'));
     }
 
+    #[test]
+    fn conflict_warning_points_at_definitions() {
+        let span = |line| Span::new(Position::new(line, 1, 0), Position::new(line, 2, 1));
+        let mut entries = Entries::new(vec![
+            EntryType::RefEntry(
+                RefEntry::new("misc".to_string(), "a".to_string(), vec![tag("note", "x")])
+                    .with_span(span(3)),
+            ),
+            EntryType::RefEntry(
+                RefEntry::new("misc".to_string(), "a".to_string(), vec![tag("note", "y")])
+                    .with_span(span(10)),
+            ),
+        ]);
+        let warnings = entries.remove_duplicates();
+        assert_eq!(
+            warnings,
+            vec![
+                "warning: cite key 'a' has 2 conflicting definitions \
+                 (at line 3, column 1; line 10, column 1); keeping all"
+            ]
+        );
+    }
+
     #[test]
     fn collapses_exact_dup_then_warns_on_remaining_conflict() {
         let mut entries = Entries::new(vec![
@@ -419,4 +1200,327 @@ mod tests {
         assert_eq!(entries.iter().count(), 2);
         assert!(warnings.is_empty());
     }
+
+    fn macro_reference(name: &str) -> Value {
+        Value::Sequence(Sequence::new(vec![Part::Value(name.to_string())]))
+    }
+
+    #[test]
+    fn factors_repeated_values_into_strings() {
+        let icml = "International Conference on Machine Learning";
+        let mut entries = Entries::new(vec![
+            EntryType::CommentEntry(CommentEntry::implicit("note".to_string())),
+            reference("a", vec![tag("booktitle", icml), tag("title", "T")]),
+            reference("b", vec![tag("booktitle", icml), tag("title", "T")]),
+            reference("c", vec![tag("booktitle", "Once")]),
+        ]);
+        assert_eq!(entries.factor_strings(2), vec!["icml"]);
+
+        let items: Vec<&EntryType> = entries.iter().collect();
+        // Defined above the comment attached to the first reference entry.
+        assert_eq!(
+            items[0],
+            &EntryType::StringEntry(StringEntry::new(tag("icml", icml)))
+        );
+        let EntryType::RefEntry(a) = items[2] else {
+            panic!("expected a reference entry");
+        };
+        assert_eq!(a.tags[0].value, macro_reference("icml"));
+        // Titles are never factored, and values below the threshold stay.
+        assert_eq!(a.tags[1], tag("title", "T"));
+        let EntryType::RefEntry(c) = items[4] else {
+            panic!("expected a reference entry");
+        };
+        assert_eq!(c.tags[0], tag("booktitle", "Once"));
+    }
+
+    #[test]
+    fn factors_only_venue_fields_once_per_entry() {
+        let mut entries = Entries::new(vec![
+            reference(
+                "a",
+                vec![
+                    tag("author", "Zed, Amy"),
+                    tag("doi", "10.1/x"),
+                    tag("address", "Paris"),
+                ],
+            ),
+            reference(
+                "b",
+                vec![
+                    tag("author", "Zed, Amy"),
+                    tag("doi", "10.1/x"),
+                    tag("series", "Paris"),
+                ],
+            ),
+            reference(
+                "c",
+                vec![tag("publisher", "ACM"), tag("organization", "ACM")],
+            ),
+        ]);
+        // `Paris` is in two entries; `ACM` twice in one entry counts once.
+        assert_eq!(entries.factor_strings(2), vec!["paris"]);
+        let EntryType::RefEntry(a) = entries.iter().nth(1).unwrap() else {
+            panic!("expected a reference entry");
+        };
+        assert_eq!(a.tags[0], tag("author", "Zed, Amy"));
+        assert_eq!(a.tags[1], tag("doi", "10.1/x"));
+        assert_eq!(a.tags[2].value, macro_reference("paris"));
+    }
+
+    #[test]
+    fn factoring_reuses_existing_strings() {
+        let mut entries = Entries::new(vec![
+            EntryType::StringEntry(StringEntry::new(tag("pub", "Springer"))),
+            reference("a", vec![tag("publisher", "Springer")]),
+        ]);
+        assert!(entries.factor_strings(2).is_empty());
+        let EntryType::RefEntry(a) = entries.iter().nth(1).unwrap() else {
+            panic!("expected a reference entry");
+        };
+        assert_eq!(a.tags[0].value, macro_reference("pub"));
+    }
+
+    fn parse(source: &str) -> Entries {
+        let tokens = crate::token::Tokenizer::new(source.chars()).tokenize();
+        crate::parse::Parser::new(tokens.into_iter())
+            .parse()
+            .unwrap()
+    }
+
+    fn tags_of(entries: &Entries, key: &str) -> Vec<String> {
+        entries
+            .iter()
+            .find_map(|e| match e {
+                EntryType::RefEntry(r) if r.key == key => Some(
+                    r.tags
+                        .iter()
+                        .map(|t| format!("{}={:?}", t.name, t.value))
+                        .collect(),
+                ),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn resolves_crossref_and_xdata_inheritance() -> Result<()> {
+        let mut entries = parse(
+            "@inproceedings{paper, title = {P}, crossref = {conf}, xdata = {pub}}\n\
+             @proceedings{conf, title = {Conf}, year = 2020, publisher = {ACM}}\n\
+             @xdata{pub, publisher = {IEEE}}\n",
+        );
+        entries.resolve_crossrefs()?;
+        assert_eq!(
+            tags_of(&entries, "paper"),
+            vec![
+                "title=Single(\"P\")",
+                "publisher=Single(\"IEEE\")",
+                "booktitle=Single(\"Conf\")",
+                "year=Integer(2020)",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resolves_crossref_chains() -> Result<()> {
+        let mut entries = parse(
+            "@inbook{a, crossref = {b}}\n\
+             @book{b, crossref = {c}, title = {B}}\n\
+             @mvbook{c, title = {C}, author = {X}}\n",
+        );
+        entries.resolve_crossrefs()?;
+        assert_eq!(
+            tags_of(&entries, "a"),
+            vec![
+                "booktitle=Single(\"B\")",
+                "maintitle=Single(\"C\")",
+                "author=Single(\"X\")",
+                "bookauthor=Single(\"X\")",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn crossref_errors() {
+        let entries = parse(
+            "@misc{conf, year = 2020}\n\
+             @misc{paper, crossref = {conf}}\n\
+             @misc{lost, crossref = {nowhere}}\n\
+             @misc{x, crossref = {y}}\n\
+             @misc{y, xdata = {x}}\n",
+        );
+        let errors = |check_order| -> Vec<String> {
+            entries
+                .check_crossrefs(check_order)
+                .iter()
+                .map(Error::message)
+                .collect()
+        };
+        assert_eq!(
+            errors(true),
+            vec![
+                "Entry `conf` is cross-referenced by `paper` but appears before it",
+                "Cross-referenced entry `nowhere` is not defined",
+                "Entries cross-reference each other in a cycle: x -> y -> x",
+            ]
+        );
+        assert_eq!(
+            errors(false),
+            vec![
+                "Cross-referenced entry `nowhere` is not defined",
+                "Entries cross-reference each other in a cycle: x -> y -> x",
+            ]
+        );
+
+        let mut entries = entries;
+        assert!(matches!(
+            entries.resolve_crossrefs(),
+            Err(Error::UndefinedCrossref(key, _)) if key == "nowhere"
+        ));
+    }
+
+    #[test]
+    fn crossref_cycles_are_found_once_in_wide_graphs() {
+        // Every entry lists every later one as xdata, so restarting the search
+        // from each entry without remembering visits would be exponential.
+        let count = 40;
+        let mut source = String::new();
+        for i in 0..count {
+            let parents: Vec<String> = (i + 1..count).map(|j| format!("e{j}")).collect();
+            source.push_str(&format!(
+                "@misc{{e{i}, xdata = {{{}}}}}\n",
+                parents.join(",")
+            ));
+        }
+        source.push_str(
+            "@misc{a, crossref = {b}}\n@misc{b, xdata = {c}}\n@misc{c, crossref = {b}}\n",
+        );
+        let entries = parse(&source);
+        let errors: Vec<String> = entries
+            .check_crossrefs(false)
+            .iter()
+            .map(Error::message)
+            .collect();
+        assert_eq!(
+            errors,
+            vec!["Entries cross-reference each other in a cycle: b -> c -> b"]
+        );
+    }
+
+    #[test]
+    fn normalizes_months() {
+        let mut entries = parse(
+            "@misc{a, month = {March}}\n\
+             @misc{b, month = \"sep\"}\n\
+             @misc{c, month = 3}\n\
+             @misc{d, month = {Spring}}\n",
+        );
+        let warnings = entries.normalize_months(MonthStyle::Macro);
+        assert_eq!(
+            warnings,
+            vec![
+                "warning: month 'Spring' of 'd' is not recognized (at line 4, column 18); \
+                 leaving it unchanged"
+            ]
+        );
+        let months: Vec<Value> = entries
+            .iter()
+            .filter_map(|e| match e {
+                EntryType::RefEntry(r) => Some(r.tags[0].value.clone()),
+                _ => None,
+            })
+            .collect();
+        let month =
+            |name: &str| Value::Sequence(Sequence::new(vec![Part::Value(name.to_string())]));
+        assert_eq!(
+            months,
+            vec![
+                month("mar"),
+                month("sep"),
+                month("mar"),
+                Value::Single("Spring".to_string())
+            ]
+        );
+
+        assert_eq!(entries.normalize_months(MonthStyle::Integer).len(), 1);
+        assert!(matches!(
+            entries.iter().next(),
+            Some(EntryType::RefEntry(r)) if r.tags[0].value == Value::Integer(3)
+        ));
+    }
+
+    #[test]
+    fn normalizes_links() {
+        let mut entries = parse(
+            "@misc{a, doi = {https://doi.org/10.1109/ABC%2F1}, url = {https://a.org/x\\_y}}\n\
+             @misc{b, note = {see https://a.org/x_y}, url = {https://doi.org/10.1/b}}\n\
+             @misc{c, doi = {10.1/C}, url = {https://dx.doi.org/10.1/c}}\n\
+             @misc{d, doi = {10.1/d}, url = {https://doi.org/10.1/other}}\n\
+             @misc{e, doi = mydoi, url = {https://doi.org/10.1/e}}\n",
+        );
+        let warnings = entries.normalize_links(true);
+        assert_eq!(
+            warnings,
+            vec![
+                "warning: url of 'd' points at DOI '10.1/other', which is not its doi; \
+                 keeping both"
+            ]
+        );
+        let tags: Vec<Vec<(String, Value)>> = entries
+            .iter()
+            .filter_map(|e| match e {
+                EntryType::RefEntry(r) => Some(
+                    r.tags
+                        .iter()
+                        .map(|t| (t.name.clone(), t.value.clone()))
+                        .collect(),
+                ),
+                _ => None,
+            })
+            .collect();
+        let tag = |name: &str, text: &str| (name.to_string(), Value::Single(text.to_string()));
+        assert_eq!(
+            tags,
+            vec![
+                vec![tag("doi", "10.1109/ABC/1"), tag("url", "https://a.org/x_y")],
+                vec![tag("note", "see https://a.org/x\\_y"), tag("doi", "10.1/b")],
+                vec![tag("doi", "10.1/C")],
+                vec![
+                    tag("doi", "10.1/d"),
+                    tag("url", "https://doi.org/10.1/other")
+                ],
+                // A macro doi is not compared, so both are kept without a
+                // warning.
+                vec![
+                    (
+                        "doi".to_string(),
+                        Value::Sequence(Sequence::new(vec![Part::Value("mydoi".to_string())]))
+                    ),
+                    tag("url", "https://doi.org/10.1/e")
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn key_case_collisions() {
+        let entries = parse(
+            "@misc{Smith2020, year = 2020}\n\
+             @misc{smith2020, year = 2021}\n\
+             @misc{Smith2020, year = 2020}\n\
+             @misc{adams2019, year = 2019}\n",
+        );
+        let errors: Vec<String> = entries
+            .check_key_case()
+            .iter()
+            .map(Error::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            vec!["Cite key `smith2020` differs only in case from `Smith2020` at line 2, column 1"]
+        );
+    }
 }
@@ -0,0 +1,154 @@
+/// Prefixes that point a DOI at a resolver rather than being part of it,
+/// matched ignoring case.
+const DOI_PREFIXES: [&str; 9] = [
+    "https://doi.org/",
+    "http://doi.org/",
+    "https://dx.doi.org/",
+    "http://dx.doi.org/",
+    "https://www.doi.org/",
+    "http://www.doi.org/",
+    "doi.org/",
+    "dx.doi.org/",
+    "doi:",
+];
+
+/// Fields that bibliography styles (via the `url` package or biblatex's
+/// verbatim fields) typeset as written, so `_` and `%` must not be escaped.
+const VERBATIM: [&str; 9] = [
+    "doi", "eprint", "file", "pdf", "url", "urlraw", "verba", "verbb", "verbc",
+];
+
+/// Whether `field` is typeset verbatim rather than as LaTeX.
+pub fn is_verbatim(field: &str) -> bool {
+    VERBATIM.contains(&field.to_lowercase().as_str())
+}
+
+/// A DOI without resolver prefixes, LaTeX escapes, or percent-escapes, e.g.
+/// `https://doi.org/10.1000%2Fabc\_1` becomes `10.1000/abc_1`. The case of
+/// the DOI itself is kept.
+///
+/// `%` is a valid character in a DOI, so percent-escapes are only decoded when
+/// the DOI was URL-encoded: when it came from a resolver URL, or when its `/`
+/// is itself encoded (`10.1000%2Fabc`). Either way the decoded DOI has a `/`
+/// and no prefix, so it is never decoded twice: `10.1000/a%2541` is left
+/// alone rather than becoming `10.1000/aA` over two runs.
+pub fn normalize_doi(text: &str) -> String {
+    let mut doi = unescape(text.trim());
+    let mut from_url = false;
+    while let Some(prefix) = DOI_PREFIXES.iter().find(|prefix| {
+        doi.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    }) {
+        from_url |= *prefix != "doi:";
+        doi = doi[prefix.len()..].trim_start().to_string();
+    }
+    if from_url || (!doi.contains('/') && doi.to_lowercase().contains("%2f")) {
+        percent_decode(&doi)
+    } else {
+        doi
+    }
+}
+
+/// The DOI a resolver URL such as `https://doi.org/10.1000/abc` points at.
+pub fn doi_from_url(url: &str) -> Option<String> {
+    let lowercase = url.trim().to_lowercase();
+    let is_resolver = DOI_PREFIXES
+        .iter()
+        .filter(|prefix| **prefix != "doi:")
+        .any(|prefix| lowercase.starts_with(prefix));
+    let doi = normalize_doi(url);
+    (is_resolver && doi.starts_with("10.")).then_some(doi)
+}
+
+/// Escape `_` and `%` in the URLs in a field that is typeset as LaTeX, where
+/// they would otherwise be a subscript and a comment. URLs already inside a
+/// `\url{...}` or `\href{...}` are left alone.
+pub fn escape_urls(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let is_url = word.contains("://") || word.starts_with("www.");
+            if !is_url || word.contains("\\url") || word.contains("\\href") {
+                return word.to_string();
+            }
+            let mut escaped = String::with_capacity(word.len());
+            let mut previous = None;
+            for c in word.chars() {
+                if matches!(c, '_' | '%') && previous != Some('\\') {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+                previous = Some(c);
+            }
+            escaped
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Remove the LaTeX escapes from `_` and `%`, which a verbatim field would
+/// otherwise show as backslashes.
+pub fn unescape(text: &str) -> String {
+    text.replace("\\_", "_").replace("\\%", "%")
+}
+
+/// Decode `%XX` escapes, keeping the text unchanged if the result is not
+/// valid UTF-8.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            // `from_str_radix` also accepts a sign, as in `%+1`.
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = escape {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("10.1109/IPDPS.2020.00050", "10.1109/IPDPS.2020.00050" ; "bare")]
+    #[test_case("https://doi.org/10.1109/ABC", "10.1109/ABC" ; "resolver")]
+    #[test_case("HTTP://DX.DOI.ORG/10.1109/abc", "10.1109/abc" ; "old resolver")]
+    #[test_case("doi: 10.1109/abc", "10.1109/abc" ; "doi scheme")]
+    #[test_case("10.1000%2Fabc%3C1%3E", "10.1000/abc<1>" ; "percent escapes")]
+    #[test_case("10.1000/abc\\_1", "10.1000/abc_1" ; "latex escape")]
+    #[test_case("10.1000/100%", "10.1000/100%" ; "bare percent")]
+    #[test_case("10.1000/a%2541", "10.1000/a%2541" ; "literal percent")]
+    #[test_case("https://doi.org/10.1000/a%2541", "10.1000/a%41" ; "encoded percent")]
+    #[test_case("10.1000%2Fa%2541", "10.1000/a%41" ; "encoded slash")]
+    #[test_case("10.1000%2Fa%252F", "10.1000/a%2F" ; "encoded slash in suffix")]
+    #[test_case("https://doi.org/10.1000/a%+1", "10.1000/a%+1" ; "signed escape")]
+    fn test_normalize_doi(input: &str, expected: &str) {
+        assert_eq!(normalize_doi(input), expected);
+        assert_eq!(normalize_doi(expected), expected, "not idempotent");
+    }
+
+    #[test_case("https://doi.org/10.1109/ABC", Some("10.1109/ABC") ; "resolver")]
+    #[test_case("https://example.com/10.1109/abc", None ; "other site")]
+    #[test_case("https://doi.org/", None ; "no doi")]
+    fn test_doi_from_url(url: &str, expected: Option<&str>) {
+        assert_eq!(doi_from_url(url).as_deref(), expected);
+    }
+
+    #[test_case("see https://a.org/x_y?q=50%", "see https://a.org/x\\_y?q=50\\%" ; "url")]
+    #[test_case("https://a.org/x\\_y", "https://a.org/x\\_y" ; "already escaped")]
+    #[test_case("\\url{https://a.org/x_y}", "\\url{https://a.org/x_y}" ; "url command")]
+    #[test_case("snake_case", "snake_case" ; "not a url")]
+    fn test_escape_urls(input: &str, expected: &str) {
+        assert_eq!(escape_urls(input), expected);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::models::{Entries, EntryType, Part, Sequence, Value};
+use crate::token::Span;
+use crate::{Error, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// BibTeX's predefined month macros, available without an `@string`.
+pub const MONTHS: [(&str, &str); 12] = [
+    ("jan", "January"),
+    ("feb", "February"),
+    ("mar", "March"),
+    ("apr", "April"),
+    ("may", "May"),
+    ("jun", "June"),
+    ("jul", "July"),
+    ("aug", "August"),
+    ("sep", "September"),
+    ("oct", "October"),
+    ("nov", "November"),
+    ("dec", "December"),
+];
+
+/// Words skipped when abbreviating a value into a macro name.
+const STOP_WORDS: [&str; 9] = ["a", "an", "and", "for", "in", "of", "on", "the", "to"];
+
+/// Generate a macro name for `text` that is not in `taken`: the acronym of its
+/// significant words (e.g. `picml` for "Proceedings of the International
+/// Conference on Machine Learning"), or its first word when that is too short,
+/// with a numeric suffix to avoid collisions. Month macros are always taken.
+pub fn generate_name(text: &str, taken: &BTreeSet<String>) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let acronym: String = words
+        .iter()
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .filter_map(|word| word.chars().next())
+        .collect();
+    let mut base = if acronym.len() >= 2 {
+        acronym
+    } else {
+        words.first().cloned().unwrap_or_default()
+    };
+    // Names must start with a letter or they would parse as numbers.
+    if !base.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        base.insert_str(0, "str");
+    }
+
+    let is_taken =
+        |name: &str| taken.contains(name) || MONTHS.iter().any(|(month, _)| *month == name);
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while is_taken(&name) {
+        name = format!("{base}{suffix}");
+        suffix += 1;
+    }
+    name
+}
+
+/// The expanded text of every `@string` macro in a file, plus the predefined
+/// month macros. Macro names are case-insensitive, as in BibTeX.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Macros {
+    // Lowercased macro name to fully expanded text.
+    values: BTreeMap<String, String>,
+}
+
+impl Macros {
+    /// Expand every `@string` definition in `entries`. Definitions may refer
+    /// to one another in any order; a later definition of the same name
+    /// replaces an earlier one, and a definition may shadow a month macro.
+    pub fn from_entries(entries: &Entries) -> Result<Self> {
+        let mut definitions: BTreeMap<String, (&Value, Span)> = BTreeMap::new();
+        for entry in entries.iter() {
+            if let EntryType::StringEntry(e) = entry {
+                let tag = e.tag();
+                definitions.insert(tag.name.to_lowercase(), (&tag.value, tag.value_span));
+            }
+        }
+
+        let mut macros = Self::default();
+        let mut resolving = Vec::new();
+        for name in definitions.keys() {
+            macros.resolve(name, &definitions, &mut resolving)?;
+        }
+        for (name, text) in MONTHS {
+            macros
+                .values
+                .entry(name.to_string())
+                .or_insert_with(|| text.to_string());
+        }
+        Ok(macros)
+    }
+
+    /// The expanded text of a macro, if it is defined.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    /// The text BibTeX sees for a value: macro references are substituted and
+    /// the parts of a `#` concatenation are joined. `span` locates the value
+    /// in error messages.
+    pub fn expand(&self, value: &Value, span: Span) -> Result<String> {
+        match value {
+            Value::Single(s) => Ok(s.clone()),
+            Value::Integer(n) => Ok(n.to_string()),
+            Value::Sequence(seq) => self.expand_sequence(seq, span),
+        }
+    }
+
+    pub fn expand_sequence(&self, seq: &Sequence, span: Span) -> Result<String> {
+        seq.parts()
+            .iter()
+            .map(|part| match part {
+                Part::Quoted(s) => Ok(s.clone()),
+                // Bare numbers are literal text, not macro references.
+                Part::Value(v) if v.chars().all(|c| c.is_ascii_digit()) => Ok(v.clone()),
+                Part::Value(v) => self
+                    .get(v)
+                    .map(str::to_string)
+                    .ok_or_else(|| Error::UndefinedMacro(v.clone(), span)),
+            })
+            .collect()
+    }
+
+    fn resolve(
+        &mut self,
+        name: &str,
+        definitions: &BTreeMap<String, (&Value, Span)>,
+        resolving: &mut Vec<String>,
+    ) -> Result<String> {
+        if let Some(text) = self.values.get(name) {
+            return Ok(text.clone());
+        }
+        if let Some(start) = resolving.iter().position(|n| n == name) {
+            let mut cycle = resolving[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(Error::MacroCycle(cycle));
+        }
+        let Some(&(value, span)) = definitions.get(name) else {
+            return MONTHS
+                .iter()
+                .find(|(month, _)| *month == name)
+                .map(|(_, text)| text.to_string())
+                .ok_or_else(|| Error::UndefinedMacro(name.to_string(), Span::default()));
+        };
+
+        resolving.push(name.to_string());
+        let text = match value {
+            Value::Sequence(seq) => {
+                let mut text = String::new();
+                for part in seq.parts() {
+                    match part {
+                        Part::Quoted(s) => text.push_str(s),
+                        Part::Value(v) if v.chars().all(|c| c.is_ascii_digit()) => {
+                            text.push_str(v);
+                        }
+                        Part::Value(v) => {
+                            let reference = v.to_lowercase();
+                            match self.resolve(&reference, definitions, resolving) {
+                                // Point at the definition that uses the macro.
+                                Err(Error::UndefinedMacro(missing, _)) => {
+                                    return Err(Error::UndefinedMacro(missing, span))
+                                }
+                                result => text.push_str(&result?),
+                            }
+                        }
+                    }
+                }
+                text
+            }
+            value => self.expand(value, span)?,
+        };
+        resolving.pop();
+
+        self.values.insert(name.to_string(), text.clone());
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::Parser;
+    use crate::token::Tokenizer;
+
+    fn parse(source: &str) -> Entries {
+        let tokens = Tokenizer::new(source.chars()).tokenize();
+        Parser::new(tokens.into_iter()).parse().unwrap()
+    }
+
+    #[test]
+    fn test_expand_definitions_in_any_order() -> Result<()> {
+        let entries =
+            parse("@string{full = jmlr # \" Volume \" # 2}\n@string{JMLR = \"Journal of ML\"}\n");
+        let macros = Macros::from_entries(&entries)?;
+        assert_eq!(macros.get("full"), Some("Journal of ML Volume 2"));
+        assert_eq!(macros.get("Jmlr"), Some("Journal of ML"));
+        assert_eq!(macros.get("mar"), Some("March"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_definition_shadows_month() -> Result<()> {
+        let entries = parse("@string{jan = \"Jan.\"}\n");
+        assert_eq!(Macros::from_entries(&entries)?.get("jan"), Some("Jan."));
+        Ok(())
+    }
+
+    #[test]
+    fn test_undefined_macro() {
+        let entries = parse("@string{a = b # \"x\"}\n");
+        let error = Macros::from_entries(&entries).unwrap_err();
+        assert!(matches!(&error, Error::UndefinedMacro(name, span)
+            if name == "b" && span.start.line == 1));
+    }
+
+    #[test]
+    fn test_generate_name() {
+        let taken = BTreeSet::from(["icml".to_string()]);
+        let name = |text| generate_name(text, &taken);
+        assert_eq!(
+            name("Proceedings of the International Conference on Machine Learning"),
+            "picml"
+        );
+        assert_eq!(
+            name("International Conference on Machine Learning"),
+            "icml2"
+        );
+        assert_eq!(name("Springer"), "springer");
+        assert_eq!(name("2020 IEEE"), "str2i");
+        assert_eq!(name("Jan"), "jan2");
+    }
+
+    #[test]
+    fn test_cycle() {
+        let entries = parse("@string{a = b}\n@string{b = \"x\" # c}\n@string{c = a}\n");
+        let error = Macros::from_entries(&entries).unwrap_err();
+        assert!(matches!(&error, Error::MacroCycle(cycle) if cycle == &["a", "b", "c", "a"]));
+    }
+}
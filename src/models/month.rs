@@ -0,0 +1,93 @@
+use crate::models::macros::MONTHS;
+use crate::models::{Part, Sequence, Value};
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// The form `month` fields are normalized to (see
+/// [`crate::models::Entries::normalize_months`]).
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum MonthStyle {
+    /// BibTeX's predefined month macros, e.g. `month = mar`.
+    Macro,
+    /// BibLaTeX's integer form, e.g. `month = 3`.
+    Integer,
+}
+
+impl MonthStyle {
+    /// `value` in this style, or `None` if it is not a recognizable month.
+    pub fn normalize(self, value: &Value) -> Option<Value> {
+        let month = month_number(value)?;
+        Some(match self {
+            Self::Macro => {
+                let (name, _) = MONTHS[month - 1];
+                Value::Sequence(Sequence::new(vec![Part::Value(name.to_string())]))
+            }
+            Self::Integer => Value::Integer(month as u64),
+        })
+    }
+}
+
+/// The month (1 to 12) a value names: a month macro, an English month name or
+/// abbreviation such as `March`, `Mar.`, or `Sept`, or a number.
+fn month_number(value: &Value) -> Option<usize> {
+    let text = match value {
+        Value::Single(s) => s.as_str(),
+        Value::Integer(n) => return usize::try_from(*n).ok().filter(|n| (1..=12).contains(n)),
+        Value::Sequence(seq) => match seq.parts().as_slice() {
+            [Part::Quoted(s) | Part::Value(s)] => s.as_str(),
+            _ => return None,
+        },
+    };
+    let text = text.trim().trim_end_matches('.').to_lowercase();
+    if let Ok(n) = text.parse::<usize>() {
+        return (1..=12).contains(&n).then_some(n);
+    }
+    MONTHS
+        .iter()
+        .position(|(abbreviation, name)| {
+            text == *abbreviation
+                || text == name.to_lowercase()
+                || (text == "sept" && *abbreviation == "sep")
+        })
+        .map(|i| i + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn single(text: &str) -> Value {
+        Value::Single(text.to_string())
+    }
+
+    fn macro_value(name: &str) -> Value {
+        Value::Sequence(Sequence::new(vec![Part::Value(name.to_string())]))
+    }
+
+    #[test_case(single("March"), Some(3) ; "name")]
+    #[test_case(single("mar"), Some(3) ; "abbreviation")]
+    #[test_case(single("Sept."), Some(9) ; "long abbreviation")]
+    #[test_case(single("03"), Some(3) ; "padded number")]
+    #[test_case(Value::Integer(12), Some(12) ; "integer")]
+    #[test_case(macro_value("Dec"), Some(12) ; "month macro")]
+    #[test_case(Value::Integer(13), None ; "out of range")]
+    #[test_case(single("Spring"), None ; "season")]
+    fn test_month_number(value: Value, expected: Option<usize>) {
+        assert_eq!(month_number(&value), expected);
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            MonthStyle::Macro.normalize(&single("March")),
+            Some(macro_value("mar"))
+        );
+        assert_eq!(
+            MonthStyle::Integer.normalize(&macro_value("mar")),
+            Some(Value::Integer(3))
+        );
+        assert_eq!(MonthStyle::Macro.normalize(&single("1~jan")), None);
+    }
+}
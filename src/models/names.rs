@@ -0,0 +1,382 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// One name in an `author` or `editor` list, split into BibTeX's four parts.
+///
+/// Each part keeps its words as written, braces included, joined by single
+/// spaces. Parts that are absent are empty.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Person {
+    pub first: String,
+    pub von: String,
+    pub last: String,
+    pub jr: String,
+}
+
+impl Person {
+    /// Parse a list of names separated by `and` at brace depth zero, e.g.
+    /// `Pauloski, J. Gregory and Ian Foster and others`.
+    pub fn parse_list(text: &str) -> Vec<Self> {
+        let mut names = vec![vec![]];
+        for token in tokens(text) {
+            match token {
+                NameToken::Word(word) if word.eq_ignore_ascii_case("and") => names.push(vec![]),
+                token => names.last_mut().expect("names is never empty").push(token),
+            }
+        }
+        names
+            .into_iter()
+            .filter(|tokens| !tokens.is_empty())
+            .map(|tokens| Self::from_tokens(&tokens))
+            .collect()
+    }
+
+    /// Parse a single name written as "First von Last", "von Last, First", or
+    /// "von Last, Jr, First".
+    pub fn parse(name: &str) -> Self {
+        Self::from_tokens(&tokens(name))
+    }
+
+    /// Whether this is the `others` that marks a truncated name list.
+    pub fn is_others(&self) -> bool {
+        self.first.is_empty()
+            && self.von.is_empty()
+            && self.jr.is_empty()
+            && self.last.eq_ignore_ascii_case("others")
+    }
+
+    /// The name as "von Last, Jr, First" in `style`, which BibTeX parses back
+    /// into the same parts.
+    pub fn format(&self, style: NameStyle) -> String {
+        if self.is_others() {
+            return "others".to_string();
+        }
+        let mut name = [self.von.as_str(), self.last.as_str()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let first = match style {
+            NameStyle::Full => self.first.clone(),
+            NameStyle::Initials => initials(&self.first, " "),
+            NameStyle::CompactInitials => initials(&self.first, ""),
+        };
+        if !self.jr.is_empty() {
+            // Keep the second comma even without first names so the jr part
+            // is not read back as the first names.
+            name = format!("{name}, {}, {first}", self.jr)
+                .trim_end()
+                .to_string();
+        } else if !first.is_empty() {
+            name = format!("{name}, {first}");
+        }
+        name
+    }
+
+    fn from_tokens(tokens: &[NameToken]) -> Self {
+        let parts: Vec<Vec<&str>> = tokens
+            .split(|token| matches!(token, NameToken::Comma))
+            .map(|part| {
+                part.iter()
+                    .filter_map(|token| match token {
+                        NameToken::Word(word) => Some(word.as_str()),
+                        NameToken::Comma => None,
+                    })
+                    .collect()
+            })
+            .collect();
+        match parts.as_slice() {
+            [] => Self::default(),
+            [words] => {
+                let Some((_, init)) = words.split_last() else {
+                    return Self::default();
+                };
+                // The von part runs from the first lowercase word to the last
+                // one, but never takes the final word.
+                let start = init.iter().position(|word| is_lowercase(word));
+                let (first, rest) = words.split_at(start.unwrap_or(init.len()));
+                let (von, last) = split_von(rest);
+                Self {
+                    first: first.join(" "),
+                    von,
+                    last,
+                    jr: String::new(),
+                }
+            }
+            [names, rest @ ..] => {
+                let (von, last) = split_von(names);
+                // BibTeX rejects more than two commas; keep the extra text in
+                // the first names rather than dropping it.
+                let (jr, first) = match rest {
+                    [first] => (String::new(), first.join(" ")),
+                    [jr, first @ ..] => (
+                        jr.join(" "),
+                        first
+                            .iter()
+                            .map(|part| part.join(" "))
+                            .collect::<Vec<String>>()
+                            .join(", "),
+                    ),
+                    [] => unreachable!("rest has at least one part"),
+                };
+                Self {
+                    first,
+                    von,
+                    last,
+                    jr,
+                }
+            }
+        }
+    }
+}
+
+/// The form `author` and `editor` lists are rewritten to: every name as
+/// "von Last, Jr, First", joined by `and`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum NameStyle {
+    /// Given names as written, e.g. `Pauloski, J. Gregory`.
+    Full,
+    /// Given names as spaced initials, e.g. `Pauloski, J. G.`.
+    Initials,
+    /// Given names as initials without spaces, e.g. `Pauloski, J.G.`.
+    CompactInitials,
+}
+
+impl NameStyle {
+    /// `text`, a list of names, in this style. Brace-protected names such as
+    /// `{IEEE Computer Society}` and `others` are kept as they are.
+    pub fn format(self, text: &str) -> String {
+        let names = Person::parse_list(text);
+        if names.is_empty() {
+            return text.to_string();
+        }
+        names
+            .iter()
+            .map(|person| person.format(self))
+            .collect::<Vec<String>>()
+            .join(" and ")
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum NameToken {
+    Word(String),
+    Comma,
+}
+
+/// Split name text into words and commas at brace depth zero. Whitespace and
+/// `~` separate words.
+fn tokens(text: &str) -> Vec<NameToken> {
+    let mut tokens = vec![];
+    let mut word = String::new();
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if depth == 0 && (c.is_whitespace() || c == '~' || c == ',') {
+            if !word.is_empty() {
+                tokens.push(NameToken::Word(std::mem::take(&mut word)));
+            }
+            if c == ',' {
+                tokens.push(NameToken::Comma);
+            }
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(NameToken::Word(word));
+    }
+    tokens
+}
+
+/// Split the words of a "von Last" part: the von part ends at the last
+/// lowercase word before the final word.
+fn split_von(words: &[&str]) -> (String, String) {
+    let Some((_, init)) = words.split_last() else {
+        return (String::new(), String::new());
+    };
+    let end = init
+        .iter()
+        .rposition(|word| is_lowercase(word))
+        .map_or(0, |i| i + 1);
+    let (von, last) = words.split_at(end);
+    (von.join(" "), last.join(" "))
+}
+
+/// Abbreviate given names to initials joined by `separator`: `Jean-Paul
+/// Gregory` becomes `J.-P. G.` with a space. Names already written as initials,
+/// such as `J.G.`, are split at their periods. A leading brace group such as
+/// `{\"E}mile` or `{Ch}ristophe` is kept whole as the initial.
+fn initials(first: &str, separator: &str) -> String {
+    first
+        .split(' ')
+        .flat_map(|word| split_outside_braces(word, '.'))
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            split_outside_braces(name, '-')
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .map(|part| format!("{}.", initial(part)))
+                .collect::<Vec<String>>()
+                .join("-")
+        })
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// The first letter of a name, or its leading brace group.
+fn initial(name: &str) -> &str {
+    if !name.starts_with('{') {
+        return name.char_indices().nth(1).map_or(name, |(i, _)| &name[..i]);
+    }
+    let mut depth = 0;
+    for (i, c) in name.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return &name[..=i];
+        }
+    }
+    name
+}
+
+/// Split `text` at each `separator` outside braces.
+fn split_outside_braces(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Whether a word starts with a lowercase letter, which makes it part of the
+/// von part. Letters inside braces do not count, except in special characters
+/// such as `{\"o}`, whose case is that of the letter they accent.
+fn is_lowercase(word: &str) -> bool {
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '{' {
+            let special = chars.peek() == Some(&'\\');
+            let mut depth = 1;
+            for c in chars.by_ref() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    c if special && c.is_alphabetic() => return c.is_lowercase(),
+                    _ => {}
+                }
+                if depth == 0 {
+                    break;
+                }
+            }
+        } else if c.is_alphabetic() {
+            return c.is_lowercase();
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn person(first: &str, von: &str, last: &str, jr: &str) -> Person {
+        Person {
+            first: first.to_string(),
+            von: von.to_string(),
+            last: last.to_string(),
+            jr: jr.to_string(),
+        }
+    }
+
+    #[test_case("Ian Foster", person("Ian", "", "Foster", "") ; "first last")]
+    #[test_case("J. Gregory Pauloski", person("J. Gregory", "", "Pauloski", "") ; "middle name")]
+    #[test_case("Ludwig van Beethoven", person("Ludwig", "van", "Beethoven", "") ; "von")]
+    #[test_case("Charles Louis Xavier Joseph de la Vall{\\'e}e Poussin", person("Charles Louis Xavier Joseph", "de la", "Vall{\\'e}e Poussin", "") ; "von and compound last")]
+    #[test_case("Foster", person("", "", "Foster", "") ; "last only")]
+    #[test_case("van Beethoven", person("", "van", "Beethoven", "") ; "von last")]
+    #[test_case("Pauloski, J. Gregory", person("J. Gregory", "", "Pauloski", "") ; "last first")]
+    #[test_case("van Beethoven, Ludwig", person("Ludwig", "van", "Beethoven", "") ; "von last first")]
+    #[test_case("De la Fontaine, Jean", person("Jean", "De la", "Fontaine", "") ; "capitalized von")]
+    #[test_case("King, Jr, Martin Luther", person("Martin Luther", "", "King", "Jr") ; "jr")]
+    #[test_case("{IEEE Computer Society}", person("", "", "{IEEE Computer Society}", "") ; "corporate")]
+    #[test_case("Jean {de la} Fontaine", person("Jean {de la}", "", "Fontaine", "") ; "braced von is not von")]
+    #[test_case("{\\\"O}stlund, Lars", person("Lars", "", "{\\\"O}stlund", "") ; "special character")]
+    #[test_case("Donald~E. Knuth", person("Donald E.", "", "Knuth", "") ; "tie")]
+    fn test_parse(name: &str, expected: Person) {
+        assert_eq!(Person::parse(name), expected);
+    }
+
+    #[test_case("{\\\"o}", true ; "lowercase special")]
+    #[test_case("{\\\"O}", false ; "uppercase special")]
+    #[test_case("{von}", false ; "braced")]
+    #[test_case("{v}on", true ; "braced prefix")]
+    #[test_case("1st", true ; "leading digit")]
+    #[test_case("2nd-{ED}", true ; "digits then letters")]
+    #[test_case("--", false ; "no letters")]
+    fn test_is_lowercase(word: &str, expected: bool) {
+        assert_eq!(is_lowercase(word), expected);
+    }
+
+    #[test_case("J. Gregory", "J. G." ; "initial and name")]
+    #[test_case("J.G.", "J. G." ; "compact initials")]
+    #[test_case("Jean-Paul", "J.-P." ; "hyphenated")]
+    #[test_case("{\\\"E}mile", "{\\\"E}." ; "special character")]
+    #[test_case("{Ch}ristophe", "{Ch}." ; "braced initial")]
+    #[test_case("", "" ; "no first names")]
+    fn test_initials(first: &str, expected: &str) {
+        assert_eq!(initials(first, " "), expected);
+    }
+
+    #[test_case("J. Gregory Pauloski and Ian Foster", NameStyle::Full, "Pauloski, J. Gregory and Foster, Ian" ; "full")]
+    #[test_case("J. Gregory Pauloski and Ian Foster", NameStyle::Initials, "Pauloski, J. G. and Foster, I." ; "initials")]
+    #[test_case("J. Gregory Pauloski and Ian Foster", NameStyle::CompactInitials, "Pauloski, J.G. and Foster, I." ; "compact initials")]
+    #[test_case("{IEEE Computer Society} and others", NameStyle::Full, "{IEEE Computer Society} and others" ; "corporate and others")]
+    #[test_case("Ludwig van Beethoven", NameStyle::Full, "van Beethoven, Ludwig" ; "von")]
+    #[test_case("King, Jr., Martin Luther", NameStyle::Initials, "King, Jr., M. L." ; "jr")]
+    #[test_case("King, Jr.,", NameStyle::Full, "King, Jr.," ; "jr without first names")]
+    #[test_case(" ", NameStyle::Full, " " ; "empty")]
+    fn test_name_style(text: &str, style: NameStyle, expected: &str) {
+        assert_eq!(style.format(text), expected);
+        assert_eq!(style.format(expected), expected, "not idempotent");
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let names = Person::parse_list(
+            "Pauloski, J. Gregory and Ian Foster AND {Barnes and Noble} and others",
+        );
+        assert_eq!(
+            names,
+            vec![
+                person("J. Gregory", "", "Pauloski", ""),
+                person("Ian", "", "Foster", ""),
+                person("", "", "{Barnes and Noble}", ""),
+                person("", "", "others", ""),
+            ]
+        );
+        assert!(names[3].is_others());
+        assert!(!names[0].is_others());
+        assert_eq!(Person::parse_list(" "), vec![]);
+    }
+}
@@ -1,14 +1,61 @@
+use crate::models::Person;
+use crate::token::Span;
 use std::cmp::{Ord, Ordering, PartialOrd};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq)]
 pub struct Tag {
     pub name: String,
     pub value: Value,
+    // Source ranges of the whole `name = value` tag and of its value alone,
+    // and the delimiter the value was written with. Default (empty) spans and
+    // no delimiter for tags not built by the parser. Excluded from equality
+    // since they are source metadata.
+    pub span: Span,
+    pub value_span: Span,
+    pub delimiter: Option<Delimiter>,
+}
+
+/// The delimiter around a single string value in the source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Delimiter {
+    /// `{...}`
+    Braces,
+    /// `"..."`
+    Quotes,
 }
 
 impl Tag {
-    pub const fn new(name: String, value: Value) -> Self {
-        Self { name, value }
+    pub fn new(name: String, value: Value) -> Self {
+        Self {
+            name,
+            value,
+            span: Span::default(),
+            value_span: Span::default(),
+            delimiter: None,
+        }
+    }
+
+    pub const fn with_spans(mut self, span: Span, value_span: Span) -> Self {
+        self.span = span;
+        self.value_span = value_span;
+        self
+    }
+
+    pub const fn with_delimiter(mut self, delimiter: Delimiter) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// The names in a name-list value such as `author` or `editor`. Macro
+    /// references in a concatenation are read as their names.
+    pub fn persons(&self) -> Vec<Person> {
+        Person::parse_list(&self.value.text())
+    }
+}
+
+impl PartialEq for Tag {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.value == other.value
     }
 }
 
@@ -39,7 +86,7 @@ impl Ord for Tag {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Value {
     Single(String),
     Integer(u64),
@@ -54,9 +101,25 @@ impl Value {
             Self::Sequence(s) => s.is_empty(),
         }
     }
+
+    /// The value as text, with the parts of a sequence concatenated and no
+    /// delimiters, e.g. `jan # "~1"` becomes `jan~1`. Macros are not expanded.
+    pub fn text(&self) -> String {
+        match self {
+            Self::Single(s) => s.clone(),
+            Self::Integer(n) => n.to_string(),
+            Self::Sequence(seq) => seq
+                .parts()
+                .iter()
+                .map(|part| match part {
+                    Part::Quoted(s) | Part::Value(s) => s.as_str(),
+                })
+                .collect(),
+        }
+    }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Sequence(Vec<Part>);
 
 impl Sequence {
@@ -85,7 +148,7 @@ impl Iterator for Sequence {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Part {
     Quoted(String),
     Value(String),
@@ -0,0 +1,412 @@
+use crate::models::{RefEntry, Tag};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// How tags missing from a [`TagOrder`] list are ordered after the listed
+/// ones.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TagFallback {
+    /// Sorted by name.
+    #[default]
+    Alphabetical,
+    /// In the order they appear in the source.
+    Original,
+}
+
+/// The order of tags within an entry.
+///
+/// Tags named in a list come first, in list order, followed by the remaining
+/// tags ordered by the fallback. A list can be set for all entries and
+/// overridden for specific entry types. Names are compared case-insensitively.
+/// The default puts `title` then `author` first and sorts the rest by name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TagOrder {
+    fields: Vec<String>,
+    by_type: BTreeMap<String, Vec<String>>,
+    fallback: TagFallback,
+}
+
+impl Default for TagOrder {
+    fn default() -> Self {
+        Self::new(["title", "author"])
+    }
+}
+
+impl TagOrder {
+    pub fn new<S: AsRef<str>>(fields: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            fields: lowercase(fields),
+            by_type: BTreeMap::new(),
+            fallback: TagFallback::default(),
+        }
+    }
+
+    /// Use `fields` instead of the default list for entries of type `kind`.
+    #[must_use]
+    pub fn with_type<S: AsRef<str>>(
+        mut self,
+        kind: &str,
+        fields: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.by_type.insert(kind.to_lowercase(), lowercase(fields));
+        self
+    }
+
+    #[must_use]
+    pub const fn with_fallback(mut self, fallback: TagFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Stably sort the tags of an entry of type `kind`.
+    pub fn sort(&self, kind: &str, tags: &mut [&Tag]) {
+        let fields = self
+            .by_type
+            .get(&kind.to_lowercase())
+            .unwrap_or(&self.fields);
+        let rank = |tag: &Tag| {
+            let name = tag.name.to_lowercase();
+            let position = fields.iter().position(|field| *field == name);
+            (position.unwrap_or(fields.len()), name)
+        };
+        tags.sort_by(|a, b| {
+            let (a_rank, a_name) = rank(a);
+            let (b_rank, b_name) = rank(b);
+            a_rank.cmp(&b_rank).then_with(|| match self.fallback {
+                TagFallback::Alphabetical => a_name.cmp(&b_name),
+                TagFallback::Original => Ordering::Equal,
+            })
+        });
+    }
+}
+
+/// How reference entries are ordered when entries are sorted. Preambles and
+/// `@string` definitions always come first, and comments move with the entry
+/// that follows them, whatever the mode.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntrySort {
+    /// By cite key, ignoring case.
+    #[default]
+    Key,
+    /// By the first author's surname, then year, then cite key.
+    Author,
+    /// By year, most recent first, then cite key.
+    Year,
+    /// Grouped by entry type, then by cite key.
+    Type,
+    /// In source order.
+    Original,
+}
+
+impl EntrySort {
+    /// Compare two reference entries on everything but the cite key. Entries
+    /// that compare equal are ordered by key (see [`KeyCollation`]) except in
+    /// `Original` mode, where a stable sort keeps the source order.
+    pub fn compare(self, a: &RefEntry, b: &RefEntry) -> Ordering {
+        match self {
+            Self::Key | Self::Original => Ordering::Equal,
+            Self::Author => {
+                last_first(surname(a), surname(b)).then_with(|| last_first(year(a), year(b)))
+            }
+            Self::Year => last_first(year(a).map(Reverse), year(b).map(Reverse)),
+            Self::Type => a.kind.to_lowercase().cmp(&b.kind.to_lowercase()),
+        }
+    }
+
+    /// Whether entries equal under [`EntrySort::compare`] are ordered by key.
+    pub fn by_key(self) -> bool {
+        self != Self::Original
+    }
+}
+
+/// How cite keys are compared when sorting. Keys are compared as emitted, so
+/// the order matches the printed keys.
+///
+/// By default keys are compared ignoring case. Natural collation compares runs
+/// of digits by their numeric value, so `paper9` sorts before `paper10`.
+/// Unicode collation also ignores accents, so `émile` sorts next to `emile`
+/// rather than after `zoe`. Keys that are otherwise equal fall back to code
+/// point order.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KeyCollation {
+    pub natural: bool,
+    pub unicode: bool,
+}
+
+impl KeyCollation {
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        let (folded_a, folded_b) = if self.unicode {
+            (fold(a), fold(b))
+        } else {
+            (a.to_lowercase(), b.to_lowercase())
+        };
+        let primary = if self.natural {
+            compare_natural(&folded_a, &folded_b)
+        } else {
+            folded_a.cmp(&folded_b)
+        };
+        primary.then_with(|| a.cmp(b))
+    }
+}
+
+/// Lowercase `key` and strip its accents by decomposing it and dropping the
+/// combining marks.
+fn fold(key: &str) -> String {
+    key.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Compare strings chunk by chunk, where a chunk is a run of ASCII digits or a
+/// run of anything else. Digit runs compare by value and other runs by code
+/// point.
+fn compare_natural(a: &str, b: &str) -> Ordering {
+    let (a, b) = (chunks(a), chunks(b));
+    for (x, y) in a.iter().zip(&b) {
+        let ordering = if is_number(x) && is_number(y) {
+            let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+            x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+        } else {
+            x.cmp(y)
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn chunks(text: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut digits = None;
+    for (i, c) in text.char_indices() {
+        let digit = c.is_ascii_digit();
+        if digits.is_some_and(|previous| previous != digit) {
+            chunks.push(&text[start..i]);
+            start = i;
+        }
+        digits = Some(digit);
+    }
+    if start < text.len() {
+        chunks.push(&text[start..]);
+    }
+    chunks
+}
+
+fn is_number(chunk: &str) -> bool {
+    chunk.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Compare present values in order, placing missing values last.
+fn last_first<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// The plain text of a field, with braces removed and macro names kept as-is.
+fn field(entry: &RefEntry, name: &str) -> Option<String> {
+    let tag = entry
+        .tags
+        .iter()
+        .find(|tag| tag.name.eq_ignore_ascii_case(name))?;
+    Some(tag.value.text().replace(['{', '}'], ""))
+}
+
+/// The surname (last part, without braces) of an entry's first author,
+/// lowercased.
+fn surname(entry: &RefEntry) -> Option<String> {
+    let first = entry.persons("author").into_iter().next()?;
+    Some(first.last.replace(['{', '}'], "").to_lowercase())
+}
+
+/// The first number in an entry's year field.
+fn year(entry: &RefEntry) -> Option<u64> {
+    let year = field(entry, "year")?;
+    let digits: String = year
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+fn lowercase<S: AsRef<str>>(fields: impl IntoIterator<Item = S>) -> Vec<String> {
+    fields
+        .into_iter()
+        .map(|field| field.as_ref().to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Value;
+
+    fn names(order: &TagOrder, kind: &str, names: &[&str]) -> Vec<String> {
+        let tags: Vec<Tag> = names
+            .iter()
+            .map(|name| Tag::new(name.to_string(), Value::Integer(0)))
+            .collect();
+        let mut refs: Vec<&Tag> = tags.iter().collect();
+        order.sort(kind, &mut refs);
+        refs.iter().map(|tag| tag.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_default_matches_title_author_first() {
+        let order = TagOrder::default();
+        assert_eq!(
+            names(&order, "misc", &["year", "Author", "doi", "title"]),
+            vec!["title", "Author", "doi", "year"]
+        );
+    }
+
+    #[test]
+    fn test_custom_list_with_original_fallback() {
+        let order = TagOrder::new(["author", "title", "year"]).with_fallback(TagFallback::Original);
+        assert_eq!(
+            names(&order, "misc", &["url", "year", "doi", "title", "author"]),
+            vec!["author", "title", "year", "url", "doi"]
+        );
+    }
+
+    #[test]
+    fn test_per_type_list() {
+        let order = TagOrder::new(["title"]).with_type("Article", ["journal", "title"]);
+        assert_eq!(
+            names(&order, "article", &["title", "journal", "abstract"]),
+            vec!["journal", "title", "abstract"]
+        );
+        assert_eq!(
+            names(&order, "book", &["journal", "title"]),
+            vec!["title", "journal"]
+        );
+    }
+
+    fn entry(kind: &str, key: &str, author: &str, year: u64) -> RefEntry {
+        let mut tags = vec![Tag::new("year".to_string(), Value::Integer(year))];
+        if !author.is_empty() {
+            tags.push(Tag::new(
+                "author".to_string(),
+                Value::Single(author.to_string()),
+            ));
+        }
+        RefEntry::new(kind.to_string(), key.to_string(), tags)
+    }
+
+    fn sorted(sort: EntrySort, entries: &[RefEntry]) -> Vec<&str> {
+        let mut entries: Vec<&RefEntry> = entries.iter().collect();
+        entries.sort_by(|a, b| {
+            sort.compare(a, b).then_with(|| {
+                if sort.by_key() {
+                    let (a, b) = (a.key.to_lowercase(), b.key.to_lowercase());
+                    KeyCollation::default().compare(&a, &b)
+                } else {
+                    Ordering::Equal
+                }
+            })
+        });
+        entries.iter().map(|e| e.key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_entry_sort_modes() {
+        let entries = [
+            entry("misc", "Zed", "Ada {Lovelace} and Alan Turing", 1843),
+            entry("article", "b", "Turing, Alan", 1950),
+            entry("article", "alpha", "", 1950),
+            entry("book", "c", "Lovelace, Ada", 1850),
+        ];
+        assert_eq!(
+            sorted(EntrySort::Key, &entries),
+            vec!["alpha", "b", "c", "Zed"]
+        );
+        assert_eq!(
+            sorted(EntrySort::Author, &entries),
+            vec!["Zed", "c", "b", "alpha"]
+        );
+        assert_eq!(
+            sorted(EntrySort::Year, &entries),
+            vec!["alpha", "b", "c", "Zed"]
+        );
+        assert_eq!(
+            sorted(EntrySort::Type, &entries),
+            vec!["alpha", "b", "c", "Zed"]
+        );
+        assert_eq!(
+            sorted(EntrySort::Original, &entries),
+            vec!["Zed", "b", "alpha", "c"]
+        );
+    }
+
+    #[test]
+    fn test_author_sort_uses_last_names() {
+        // The von part is not part of the surname, so "van Beethoven" sorts under B.
+        let entries = [
+            entry("misc", "a", "Ludwig van Beethoven", 1800),
+            entry("misc", "b", "Clara Schumann and Robert Schumann", 1840),
+            entry("misc", "c", "{Anonymous Society}", 1900),
+            entry("misc", "d", "Foster, Ian", 2000),
+        ];
+        assert_eq!(
+            sorted(EntrySort::Author, &entries),
+            vec!["c", "a", "d", "b"]
+        );
+    }
+
+    fn collated(collation: KeyCollation, keys: &[&'static str]) -> Vec<&'static str> {
+        let mut keys = keys.to_vec();
+        keys.sort_by(|a, b| collation.compare(a, b));
+        keys
+    }
+
+    #[test]
+    fn test_key_collation() {
+        let keys = ["paper10", "zoe", "paper9", "émile", "emile2", "Emile"];
+        let plain = KeyCollation::default();
+        assert_eq!(
+            collated(plain, &keys),
+            vec!["Emile", "emile2", "paper10", "paper9", "zoe", "émile"]
+        );
+        let natural = KeyCollation {
+            natural: true,
+            ..plain
+        };
+        assert_eq!(
+            collated(natural, &keys),
+            vec!["Emile", "emile2", "paper9", "paper10", "zoe", "émile"]
+        );
+        let unicode = KeyCollation {
+            unicode: true,
+            ..plain
+        };
+        assert_eq!(
+            collated(unicode, &keys),
+            vec!["Emile", "émile", "emile2", "paper10", "paper9", "zoe"]
+        );
+    }
+
+    #[test]
+    fn test_natural_collation_numbers() {
+        let natural = KeyCollation {
+            natural: true,
+            unicode: false,
+        };
+        assert_eq!(
+            collated(natural, &["a10b", "a2b", "a02", "a", "a2"]),
+            vec!["a", "a02", "a2", "a2b", "a10b"]
+        );
+    }
+}
@@ -1,22 +1,36 @@
-use crate::models::{CommentEntry, Entries, EntryType, PreambleEntry, RefEntry, StringEntry};
-use crate::models::{Part, Sequence, Tag, Value};
-use crate::token::{stringify, Position, Special, Token, TokenInfo, Whitespace};
+use crate::models::{
+    CommentEntry, Entries, EntryType, PreambleEntry, RawEntry, RefEntry, StringEntry,
+};
+use crate::models::{Delimiter, Part, Sequence, Tag, Value};
+use crate::token::{stringify, Position, Span, Special, Token, TokenInfo, Whitespace};
 use crate::{Error, Result};
-use std::iter::Peekable;
+use std::collections::VecDeque;
 
 pub struct Parser<I>
 where
     I: Iterator<Item = TokenInfo>,
 {
-    tokens: Peekable<I>,
+    tokens: I,
+    // Tokens pulled from `tokens` but not yet consumed: filled by `peek` and by
+    // error recovery handing back tokens a malformed entry read past.
+    lookahead: VecDeque<TokenInfo>,
+    // Every token consumed since the start of the current entry, so that a
+    // malformed entry can be kept verbatim when recovering.
+    consumed: Vec<TokenInfo>,
     position: Position,
+    // End of the last non-whitespace token consumed, used to close spans so
+    // that they never include trailing whitespace.
+    end: Position,
 }
 
 impl<I: Iterator<Item = TokenInfo>> Parser<I> {
-    pub fn new(iter: I) -> Self {
+    pub const fn new(iter: I) -> Self {
         Self {
-            tokens: iter.peekable(),
-            position: Position { line: 0, column: 0 },
+            tokens: iter,
+            lookahead: VecDeque::new(),
+            consumed: Vec::new(),
+            position: Position::new(0, 0, 0),
+            end: Position::new(0, 0, 0),
         }
     }
 
@@ -29,7 +43,11 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
     }
 
     fn peek(&mut self) -> Option<&TokenInfo> {
-        self.tokens.peek()
+        if self.lookahead.is_empty() {
+            let info = self.tokens.next()?;
+            self.lookahead.push_back(info);
+        }
+        self.lookahead.front()
     }
 
     fn peek_non_whitespace(&mut self) -> Option<TokenInfo> {
@@ -43,29 +61,47 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
     }
 
     fn next(&mut self) -> Option<TokenInfo> {
-        if let Some(info) = self.tokens.next() {
-            self.position = info.position;
-            Some(info)
-        } else {
-            None
+        let info = self.lookahead.pop_front().or_else(|| self.tokens.next())?;
+        self.position = info.position;
+        if !info.is_whitespace() {
+            self.end = info.end();
         }
+        self.consumed.push(info.clone());
+        Some(info)
     }
 
     fn next_non_whitespace(&mut self) -> Option<TokenInfo> {
-        if let Some(info) = self
-            .tokens
-            .find(|token_info| !matches!(token_info.value, Token::Whitespace(_)))
-        {
-            self.position = info.position;
-            Some(info)
-        } else {
-            None
+        while let Some(info) = self.next() {
+            if !info.is_whitespace() {
+                return Some(info);
+            }
         }
+        None
+    }
+
+    /// The span from `start` through the last non-whitespace token consumed.
+    const fn span_from(&self, start: Position) -> Span {
+        Span::new(start, self.end)
     }
 
+    /// Parse the token stream, failing on the first malformed entry.
     pub fn parse(&mut self) -> Result<Entries> {
+        let (entries, errors) = self.parse_recovering();
+        errors.into_iter().next().map_or(Ok(entries), Err)
+    }
+
+    /// Parse the token stream, recovering from malformed entries rather than
+    /// stopping at the first one.
+    ///
+    /// When an entry fails to parse, the error is recorded and the parser
+    /// resynchronizes at the next top-level `@` (see [`Resync`]). The skipped
+    /// region is kept verbatim as a [`RawEntry`] so that no source text is lost.
+    /// Returns the partial entries alongside every error encountered, in source
+    /// order.
+    pub fn parse_recovering(&mut self) -> (Entries, Vec<Error>) {
         let mut entries: Vec<EntryType> = Vec::new();
         let mut leadings: Vec<String> = Vec::new();
+        let mut errors: Vec<Error> = Vec::new();
         // Whitespace an implicit comment swallowed while scanning up to the next
         // `@`; it belongs to the element that follows the comment.
         let mut pending_leading = String::new();
@@ -79,7 +115,16 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
             match self.peek() {
                 None => break,
                 Some(info) if info.value == Token::Special(Special::At) => {
-                    entries.push(self.parse_entry()?);
+                    self.consumed.clear();
+                    match self.parse_entry() {
+                        Ok(entry) => entries.push(entry),
+                        Err(error) => {
+                            errors.push(error);
+                            let (raw, trailing) = self.recover();
+                            entries.push(EntryType::RawEntry(raw));
+                            pending_leading = trailing;
+                        }
+                    }
                     leadings.push(leading);
                 }
                 Some(_) => {
@@ -91,7 +136,50 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
             }
         }
 
-        Ok(Entries::with_leading(entries, leadings))
+        (Entries::with_leading(entries, leadings), errors)
+    }
+
+    /// Skip past a malformed entry, returning its raw text and the trailing
+    /// whitespace it swallowed (as with [`Self::parse_implicit_comment`]).
+    ///
+    /// The failed parse may already have read past the point where the next
+    /// entry starts (e.g. an entry missing its closing brace), so the tokens it
+    /// consumed are scanned first and any that follow a boundary are handed back
+    /// to the parser.
+    fn recover(&mut self) -> (RawEntry, String) {
+        let mut consumed = std::mem::take(&mut self.consumed);
+        let start = consumed.first().map_or(self.position, |info| info.position);
+        let mut resync = Resync::default();
+
+        // Skip the malformed entry's own `@`.
+        let boundary = consumed
+            .iter()
+            .skip(1)
+            .position(|info| resync.is_boundary(info))
+            .map(|i| i + 1);
+
+        if let Some(boundary) = boundary {
+            for info in consumed.drain(boundary..).rev() {
+                self.lookahead.push_front(info);
+            }
+        } else {
+            while let Some(info) = self.peek() {
+                if resync.is_boundary(info) {
+                    break;
+                }
+                if let Some(info) = self.next() {
+                    consumed.push(info);
+                }
+            }
+        }
+
+        let end = consumed
+            .iter()
+            .rfind(|info| !info.is_whitespace())
+            .map_or(start, TokenInfo::end);
+        let raw = stringify(consumed.into_iter().map(|info| info.value).collect());
+        let (body, trailing) = split_trailing_whitespace(raw);
+        (RawEntry::new(body, Span::new(start, end)), trailing)
     }
 
     fn consume_whitespace(&mut self) -> String {
@@ -110,6 +198,7 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
 
     fn parse_entry(&mut self) -> Result<EntryType> {
         self.expect(Token::Special(Special::At))?;
+        let start = self.position;
 
         let token_info = match self.next_non_whitespace() {
             Some(token) => token,
@@ -121,20 +210,54 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
             _ => return Err(Error::MissingEntryType(token_info)),
         };
 
+        // Entries may be delimited by either braces or parentheses; the
+        // closing token must match whichever one opened the entry.
+        let close = self.parse_entry_open()?;
+
         let entry = match kind.to_lowercase().as_str() {
-            "comment" => EntryType::CommentEntry(self.parse_comment_entry()?),
-            "preamble" => EntryType::PreambleEntry(self.parse_preamble_entry()?),
-            "string" => EntryType::StringEntry(self.parse_string_entry()?),
-            _ => EntryType::RefEntry(self.parse_ref_entry(kind)?),
+            "comment" => {
+                let entry = self.parse_comment_entry(&close)?.with_keyword(kind);
+                EntryType::CommentEntry(entry.with_span(self.span_from(start)))
+            }
+            "preamble" => {
+                let entry = self.parse_preamble_entry(&close)?.with_keyword(kind);
+                EntryType::PreambleEntry(entry.with_span(self.span_from(start)))
+            }
+            "string" => {
+                let entry = self.parse_string_entry(&close)?.with_keyword(kind);
+                EntryType::StringEntry(entry.with_span(self.span_from(start)))
+            }
+            _ => {
+                let entry = self.parse_ref_entry(kind, &close)?;
+                EntryType::RefEntry(entry.with_span(self.span_from(start)))
+            }
         };
 
         Ok(entry)
     }
 
+    /// Consume the delimiter opening an entry's body and return the token that
+    /// must close it.
+    fn parse_entry_open(&mut self) -> Result<Token> {
+        match self.next_non_whitespace() {
+            Some(token_info) => match token_info.value {
+                Token::Special(Special::BraceLeft) => Ok(Token::Special(Special::BraceRight)),
+                Token::Special(Special::ParenLeft) => Ok(Token::Special(Special::ParenRight)),
+                _ => Err(Error::UnexpectedToken(
+                    Token::Special(Special::BraceLeft),
+                    token_info,
+                )),
+            },
+            None => Err(Error::EndOfTokenStream(self.position)),
+        }
+    }
+
     /// Parse free text between entries into an implicit comment, returning it
     /// alongside the trailing whitespace it consumed (up to the next `@`) so the
     /// caller can attribute that whitespace to the following element.
     fn parse_implicit_comment(&mut self) -> (CommentEntry, String) {
+        let fallback = self.position;
+        let start = self.peek().map_or(fallback, |info| info.position);
         let mut tokens: Vec<Token> = Vec::new();
         while let Some(info) = self.peek() {
             if info.value == Token::Special(Special::At) {
@@ -146,32 +269,28 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
         }
         // The leading whitespace was already consumed by the caller, so the raw
         // text starts at the body. Split off the trailing whitespace.
-        let raw = stringify(tokens);
-        let body = raw.trim_end();
-        let trailing = raw[body.len()..].to_string();
-        (CommentEntry::implicit(body.to_string()), trailing)
+        let (body, trailing) = split_trailing_whitespace(stringify(tokens));
+        let span = self.span_from(start);
+        (CommentEntry::implicit(body).with_span(span), trailing)
     }
 
-    fn parse_comment_entry(&mut self) -> Result<CommentEntry> {
-        self.expect(Token::Special(Special::BraceLeft))?;
-
+    fn parse_comment_entry(&mut self, close: &Token) -> Result<CommentEntry> {
+        let open = opening(close);
         let mut nested = 0;
         let mut tokens: Vec<Token> = Vec::new();
 
         loop {
             match self.next() {
-                Some(info) => match info.value {
-                    Token::Special(Special::BraceRight) if nested == 0 => break,
-                    Token::Special(Special::BraceRight) => {
-                        nested -= 1;
-                        tokens.push(info.value);
-                    }
-                    Token::Special(Special::BraceLeft) => {
-                        nested += 1;
-                        tokens.push(info.value);
-                    }
-                    value => tokens.push(value),
-                },
+                Some(info) if info.value == *close && nested == 0 => break,
+                Some(info) if info.value == *close => {
+                    nested -= 1;
+                    tokens.push(info.value);
+                }
+                Some(info) if info.value == open => {
+                    nested += 1;
+                    tokens.push(info.value);
+                }
+                Some(info) => tokens.push(info.value),
                 None => return Err(Error::EndOfTokenStream(self.position)),
             }
         }
@@ -179,51 +298,35 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
         Ok(CommentEntry::explicit(stringify(tokens)))
     }
 
-    fn parse_preamble_entry(&mut self) -> Result<PreambleEntry> {
-        self.expect(Token::Special(Special::BraceLeft))?;
+    fn parse_preamble_entry(&mut self, close: &Token) -> Result<PreambleEntry> {
         let seq = self.parse_tag_value_sequence()?;
-        self.expect(Token::Special(Special::BraceRight))?;
+        self.expect(close.clone())?;
         Ok(PreambleEntry::new(seq))
     }
 
-    fn parse_string_entry(&mut self) -> Result<StringEntry> {
-        self.expect(Token::Special(Special::BraceLeft))?;
-
+    fn parse_string_entry(&mut self, close: &Token) -> Result<StringEntry> {
         let tag = self.parse_tag()?;
 
-        // Ignore optional trailing comma and check for closing brace.
+        // Ignore optional trailing comma and check for the closing delimiter.
         match self.next_non_whitespace() {
-            Some(token) if token.value == Token::Special(Special::BraceRight) => (),
+            Some(token) if token.value == *close => (),
             Some(token) if token.value == Token::Special(Special::Comma) => {
-                self.expect(Token::Special(Special::BraceRight))?;
-            }
-            Some(token) => {
-                return Err(Error::UnexpectedToken(
-                    Token::Special(Special::BraceRight),
-                    token,
-                ));
+                self.expect(close.clone())?;
             }
+            Some(token) => return Err(Error::UnexpectedToken(close.clone(), token)),
             None => return Err(Error::EndOfTokenStream(self.position)),
         }
 
         Ok(StringEntry::new(tag))
     }
 
-    fn parse_ref_entry(&mut self, kind: String) -> Result<RefEntry> {
-        self.expect(Token::Special(Special::BraceLeft))?;
-
-        let key = match self.next_non_whitespace() {
-            Some(token) => match token.value {
-                Token::Value(key) => key,
-                _ => return Err(Error::MissingCiteKey(token)),
-            },
-            None => return Err(Error::EndOfTokenStream(self.position)),
-        };
+    fn parse_ref_entry(&mut self, kind: String, close: &Token) -> Result<RefEntry> {
+        let key = self.parse_cite_key(close)?;
 
         let mut tags: Vec<Tag> = Vec::new();
         loop {
             match self.peek_non_whitespace() {
-                Some(token) if token.value == Token::Special(Special::BraceRight) => {
+                Some(token) if token.value == *close => {
                     self.next_non_whitespace();
                     break;
                 }
@@ -239,12 +342,42 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
         Ok(RefEntry::new(kind, key, tags))
     }
 
+    /// Parse a cite key. Parentheses are tokenized as specials, so a key such
+    /// as `foo(1)` arrives as several adjacent tokens which are glued back
+    /// together, stopping at whitespace, a comma, or the entry's closing token.
+    fn parse_cite_key(&mut self, close: &Token) -> Result<String> {
+        let mut key = match self.next_non_whitespace() {
+            Some(token) => match token.value {
+                Token::Value(key) => key,
+                _ => return Err(Error::MissingCiteKey(token)),
+            },
+            None => return Err(Error::EndOfTokenStream(self.position)),
+        };
+
+        while let Some(info) = self.peek() {
+            let glued = match &info.value {
+                Token::Value(_) => true,
+                Token::Special(Special::ParenLeft | Special::ParenRight) => info.value != *close,
+                _ => false,
+            };
+            if !glued {
+                break;
+            }
+            if let Some(info) = self.next() {
+                key.push_str(&info.value.to_string());
+            }
+        }
+
+        Ok(key)
+    }
+
     fn parse_tag(&mut self) -> Result<Tag> {
         let token_info = match self.next_non_whitespace() {
             Some(token) => token,
             None => return Err(Error::EndOfTokenStream(self.position)),
         };
 
+        let start = token_info.position;
         let name = match token_info.value {
             Token::Value(name) => name,
             _ => return Err(Error::MissingTagName(token_info)),
@@ -252,9 +385,22 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
 
         self.expect(Token::Special(Special::Equals))?;
 
+        let (value_start, delimiter) =
+            self.peek_non_whitespace()
+                .map_or((self.position, None), |info| match info.value {
+                    Token::Special(Special::BraceLeft) => (info.position, Some(Delimiter::Braces)),
+                    Token::Special(Special::Quote) => (info.position, Some(Delimiter::Quotes)),
+                    _ => (info.position, None),
+                });
         let value = self.parse_tag_value()?;
 
-        Ok(Tag::new(name, value))
+        let span = self.span_from(start);
+        let value_span = self.span_from(value_start);
+        let tag = Tag::new(name, value).with_spans(span, value_span);
+        Ok(match delimiter {
+            Some(delimiter) => tag.with_delimiter(delimiter),
+            None => tag,
+        })
     }
 
     fn parse_tag_value(&mut self) -> Result<Value> {
@@ -312,7 +458,7 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
         loop {
             if let Some(token_info) = self.peek_non_whitespace() {
                 match token_info.value {
-                    Token::Special(Special::BraceRight) => break,
+                    Token::Special(Special::BraceRight | Special::ParenRight) => break,
                     Token::Special(Special::Comma) => break,
                     Token::Special(Special::Pound) => {
                         self.expect(Token::Special(Special::Pound))?;
@@ -377,8 +523,15 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
 
         loop {
             if let Some(token) = self.next() {
-                if start == end && token.value == end {
-                    break;
+                if start == end {
+                    // Quoted strings do not nest, but a quote inside braces,
+                    // e.g. `"a {"} b"`, does not end one.
+                    match token.value {
+                        Token::Special(Special::BraceLeft) => nested += 1,
+                        Token::Special(Special::BraceRight) => nested -= 1,
+                        _ if token.value == end && nested == 0 => break,
+                        _ => (),
+                    }
                 } else if token.value == start {
                     nested += 1;
                 } else if token.value == end && nested == 0 {
@@ -403,19 +556,62 @@ impl<I: Iterator<Item = TokenInfo>> Parser<I> {
     }
 }
 
+/// Tracks where a malformed entry ends when recovering from a parse error.
+///
+/// A top-level `@` marks the start of the next entry: one outside any braces
+/// opened since the malformed entry began, or one that is the first
+/// non-whitespace token on its line. The latter lets recovery stop at the next
+/// entry even when the malformed one never closed its braces.
+#[derive(Default)]
+struct Resync {
+    depth: i32,
+    line_start: bool,
+}
+
+impl Resync {
+    /// Feed the next token, returning whether it is a top-level `@`.
+    const fn is_boundary(&mut self, info: &TokenInfo) -> bool {
+        match info.value {
+            Token::Special(Special::At) if self.depth <= 0 || self.line_start => return true,
+            Token::Special(Special::BraceLeft) => self.depth += 1,
+            Token::Special(Special::BraceRight) => self.depth -= 1,
+            _ => (),
+        }
+        self.line_start = match info.value {
+            Token::Whitespace(Whitespace::NewLine) => true,
+            Token::Whitespace(_) => self.line_start,
+            _ => false,
+        };
+        false
+    }
+}
+
+/// Split a run of raw text into its body and the whitespace trailing it.
+fn split_trailing_whitespace(raw: String) -> (String, String) {
+    let body = raw.trim_end().len();
+    let mut body_text = raw;
+    let trailing = body_text.split_off(body);
+    (body_text, trailing)
+}
+
+/// The token opening a delimited region closed by `close`.
+const fn opening(close: &Token) -> Token {
+    match close {
+        Token::Special(Special::ParenRight) => Token::Special(Special::ParenLeft),
+        _ => Token::Special(Special::BraceLeft),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::models::CommentKind;
 
     fn as_iter(tokens: Vec<Token>) -> impl Iterator<Item = TokenInfo> {
-        tokens.into_iter().enumerate().map(|(i, token)| TokenInfo {
-            value: token,
-            position: Position {
-                line: i as u32,
-                column: 0,
-            },
-        })
+        tokens
+            .into_iter()
+            .enumerate()
+            .map(|(i, token)| TokenInfo::new(token, Position::new(i as u32, 0, i)))
     }
 
     #[test]
@@ -795,6 +991,80 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_ref_entry_parens() -> Result<()> {
+        let tokens = vec![
+            Token::Special(Special::At),
+            Token::Value("misc".to_string()),
+            Token::Special(Special::ParenLeft),
+            Token::Value("citekey".to_string()),
+            Token::Special(Special::Comma),
+            Token::Value("title".to_string()),
+            Token::Special(Special::Equals),
+            Token::Special(Special::BraceLeft),
+            Token::Value("a".to_string()),
+            Token::Special(Special::ParenRight),
+            Token::Special(Special::BraceRight),
+            Token::Special(Special::ParenRight),
+        ];
+        let mut parser = Parser::new(as_iter(tokens));
+
+        let entry = parser.parse_entry()?;
+        let expected = EntryType::RefEntry(RefEntry::new(
+            "misc".to_string(),
+            "citekey".to_string(),
+            vec![Tag::new(
+                "title".to_string(),
+                Value::Single("a)".to_string()),
+            )],
+        ));
+        assert_eq!(entry, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_ref_entry_mismatched_delimiters() -> Result<()> {
+        let tokens = vec![
+            Token::Special(Special::At),
+            Token::Value("misc".to_string()),
+            Token::Special(Special::ParenLeft),
+            Token::Value("citekey".to_string()),
+            Token::Special(Special::BraceRight),
+        ];
+        let mut parser = Parser::new(as_iter(tokens));
+
+        let result = parser.parse();
+        assert!(matches!(result, Err(Error::MissingTagName(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_cite_key_with_parens() -> Result<()> {
+        let tokens = vec![
+            Token::Special(Special::At),
+            Token::Value("misc".to_string()),
+            Token::Special(Special::BraceLeft),
+            Token::Value("foo".to_string()),
+            Token::Special(Special::ParenLeft),
+            Token::Value("1".to_string()),
+            Token::Special(Special::ParenRight),
+            Token::Special(Special::BraceRight),
+        ];
+        let mut parser = Parser::new(as_iter(tokens));
+
+        let entry = parser.parse_entry()?;
+        let expected = EntryType::RefEntry(RefEntry::new(
+            "misc".to_string(),
+            "foo(1)".to_string(),
+            Vec::with_capacity(0),
+        ));
+        assert_eq!(entry, expected);
+
+        Ok(())
+    }
+
     #[test]
     fn test_parse_ref_entry_no_tags() -> Result<()> {
         let tokens = vec![
@@ -1039,7 +1309,12 @@ mod tests {
                 Token::Special(Special::Equals),
                 TokenInfo {
                     value: Token::Special(Special::BraceRight),
-                    position: Position { line: 6, column: 0 },
+                    position: Position {
+                        line: 6,
+                        column: 0,
+                        offset: 6
+                    },
+                    ..
                 },
             ))
         ));
@@ -1047,6 +1322,139 @@ mod tests {
         Ok(())
     }
 
+    fn tokenize(text: &str) -> Vec<TokenInfo> {
+        crate::token::Tokenizer::new(text.chars()).tokenize()
+    }
+
+    #[test]
+    fn test_parse_recovering_keeps_later_entries() {
+        let text = "@misc{a,\n  title = = {A},\n}\n\n@misc{b,}\n\n@misc{c x}\n";
+        let mut parser = Parser::new(tokenize(text).into_iter());
+
+        let (entries, errors) = parser.parse_recovering();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], Error::MissingContent(_)));
+        assert!(matches!(errors[1], Error::UnexpectedToken(_, _)));
+        let expected = Entries::new(vec![
+            EntryType::RawEntry(RawEntry::new(
+                "@misc{a,\n  title = = {A},\n}".to_string(),
+                Span::default(),
+            )),
+            EntryType::RefEntry(RefEntry::new(
+                "misc".to_string(),
+                "b".to_string(),
+                Vec::with_capacity(0),
+            )),
+            EntryType::RawEntry(RawEntry::new("@misc{c x}".to_string(), Span::default())),
+        ]);
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn test_parse_recovering_unclosed_entry() {
+        // The first entry never closes, so its parse reads into the second;
+        // recovery hands the second entry back at the line-leading `@`.
+        let text = "@misc{a,\n  title = {A},\n\n@misc{b,\n  title = {B},\n}";
+        let mut parser = Parser::new(tokenize(text).into_iter());
+
+        let (entries, errors) = parser.parse_recovering();
+        assert_eq!(errors.len(), 1);
+        let items: Vec<(&str, &EntryType)> = entries.iter_with_leading().collect();
+        assert_eq!(items.len(), 2);
+        assert!(
+            matches!(items[0].1, EntryType::RawEntry(r) if r.body() == "@misc{a,\n  title = {A},")
+        );
+        assert_eq!(items[1].0, "\n\n");
+        assert!(matches!(items[1].1, EntryType::RefEntry(r) if r.key == "b"));
+    }
+
+    #[test]
+    fn test_parse_recovering_skips_nested_at() {
+        // An `@` inside braces does not end the malformed region.
+        let text = "@misc{a, note = {x@y} oops}\n@misc{b}";
+        let mut parser = Parser::new(tokenize(text).into_iter());
+
+        let (entries, errors) = parser.parse_recovering();
+        assert_eq!(errors.len(), 1);
+        let expected = Entries::new(vec![
+            EntryType::RawEntry(RawEntry::new(
+                "@misc{a, note = {x@y} oops}".to_string(),
+                Span::default(),
+            )),
+            EntryType::RefEntry(RefEntry::new(
+                "misc".to_string(),
+                "b".to_string(),
+                Vec::with_capacity(0),
+            )),
+        ]);
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn test_parse_recovering_keeps_raw_text_exactly() {
+        let text = "@misc{a,\r\n\ttitle = = {A},\r\n}\r\n@misc{b}";
+        let mut parser = Parser::new(tokenize(text).into_iter());
+
+        let (entries, _) = parser.parse_recovering();
+        let items: Vec<(&str, &EntryType)> = entries.iter_with_leading().collect();
+        assert!(
+            matches!(items[0].1, EntryType::RawEntry(r) if r.body() == "@misc{a,\r\n\ttitle = = {A},\r\n}")
+        );
+        assert_eq!(items[1].0, "\r\n");
+    }
+
+    #[test]
+    fn test_parse_spans() -> Result<()> {
+        let text = "@misc{a,\n  title = {A},\n  year = 2020\n}\nnote\n";
+        let mut parser = Parser::new(tokenize(text).into_iter());
+
+        let entries = parser.parse()?;
+        let items: Vec<&EntryType> = entries.iter().collect();
+        let EntryType::RefEntry(entry) = items[0] else {
+            panic!("expected a reference entry");
+        };
+        assert_eq!(
+            entry.span,
+            Span::new(Position::new(1, 1, 0), Position::new(4, 2, 39))
+        );
+        assert_eq!(
+            &text[entry.tags[0].span.start.offset..entry.tags[0].span.end.offset],
+            "title = {A}"
+        );
+        assert_eq!(
+            entry.tags[0].value_span,
+            Span::new(Position::new(2, 11, 19), Position::new(2, 14, 22))
+        );
+        let span = entry.tags[1].value_span;
+        assert_eq!(&text[span.start.offset..span.end.offset], "2020");
+        let span = items[1].span();
+        assert_eq!(&text[span.start.offset..span.end.offset], "note");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_recovering_raw_span() {
+        let text = "@misc{a, x}\n\n@misc{b}";
+        let mut parser = Parser::new(tokenize(text).into_iter());
+
+        let (entries, _) = parser.parse_recovering();
+        let span = entries.iter().next().map(EntryType::span).unwrap();
+        assert_eq!(
+            span,
+            Span::new(Position::new(1, 1, 0), Position::new(1, 12, 11))
+        );
+    }
+
+    #[test]
+    fn test_parse_returns_first_error() {
+        let text = "@misc{}\n@misc{b, x}";
+        let mut parser = Parser::new(tokenize(text).into_iter());
+
+        let result = parser.parse();
+        assert!(matches!(result, Err(Error::MissingCiteKey(_))));
+    }
+
     #[test]
     fn test_parse_delimited_string() -> Result<()> {
         let tokens = vec![
@@ -1086,4 +1494,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_delimited_string_quote_in_braces() -> Result<()> {
+        let tokens = vec![
+            Token::Special(Special::Quote),
+            Token::Value("5".to_string()),
+            Token::Special(Special::BraceLeft),
+            Token::Special(Special::Quote),
+            Token::Special(Special::BraceRight),
+            Token::Special(Special::Quote),
+        ];
+        let mut parser = Parser::new(as_iter(tokens));
+
+        let parsed = parser.parse_delimited_string(
+            Token::Special(Special::Quote),
+            Token::Special(Special::Quote),
+        )?;
+        assert_eq!(parsed, "5{\"}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_tag_records_delimiter() -> Result<()> {
+        let text = "@misc{a, b = {x}, c = \"y\", d = 1}";
+        let entries = Parser::new(tokenize(text).into_iter()).parse()?;
+        let Some(EntryType::RefEntry(entry)) = entries.iter().next() else {
+            panic!("expected a reference entry");
+        };
+        let delimiters: Vec<Option<Delimiter>> =
+            entry.tags.iter().map(|tag| tag.delimiter).collect();
+        assert_eq!(
+            delimiters,
+            vec![Some(Delimiter::Braces), Some(Delimiter::Quotes), None]
+        );
+
+        Ok(())
+    }
 }
@@ -8,6 +8,8 @@ pub enum Special {
     BraceRight,
     Comma,
     Equals,
+    ParenLeft,
+    ParenRight,
     Pound,
     Quote,
 }
@@ -20,6 +22,8 @@ impl Special {
             '}' => Some(Self::BraceRight),
             ',' => Some(Self::Comma),
             '=' => Some(Self::Equals),
+            '(' => Some(Self::ParenLeft),
+            ')' => Some(Self::ParenRight),
             '#' => Some(Self::Pound),
             '"' => Some(Self::Quote),
             _ => None,
@@ -27,7 +31,7 @@ impl Special {
     }
 
     pub const fn is_special(c: &char) -> bool {
-        matches!(c, '@' | '{' | '}' | ',' | '=' | '#' | '"')
+        matches!(c, '@' | '{' | '}' | ',' | '=' | '(' | ')' | '#' | '"')
     }
 
     pub const fn as_char(&self) -> char {
@@ -37,6 +41,8 @@ impl Special {
             Self::BraceRight => '}',
             Self::Comma => ',',
             Self::Equals => '=',
+            Self::ParenLeft => '(',
+            Self::ParenRight => ')',
             Self::Pound => '#',
             Self::Quote => '"',
         }
@@ -45,26 +51,34 @@ impl Special {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Whitespace {
+    CarriageReturn,
     NewLine,
     Space,
     Tab,
+    // Any other whitespace character (e.g., a non-breaking space), kept as-is
+    // so that stringifying tokens reproduces the source exactly.
+    Other(char),
 }
 
 impl Whitespace {
     pub const fn from(c: &char) -> Option<Self> {
         match c {
-            '\n' | '\r' => Some(Self::NewLine),
+            '\n' => Some(Self::NewLine),
+            '\r' => Some(Self::CarriageReturn),
             '\t' => Some(Self::Tab),
-            c if c.is_whitespace() => Some(Self::Space),
+            ' ' => Some(Self::Space),
+            c if c.is_whitespace() => Some(Self::Other(*c)),
             _ => None,
         }
     }
 
     pub const fn as_char(&self) -> char {
         match self {
+            Self::CarriageReturn => '\r',
             Self::NewLine => '\n',
             Self::Space => ' ',
             Self::Tab => '\t',
+            Self::Other(c) => *c,
         }
     }
 }
@@ -86,15 +100,21 @@ impl fmt::Display for Token {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct Position {
     pub line: u32,
     pub column: u32,
+    /// Byte offset from the start of the source.
+    pub offset: usize,
 }
 
 impl Position {
-    pub const fn new(line: u32, column: u32) -> Self {
-        Self { line, column }
+    pub const fn new(line: u32, column: u32, offset: usize) -> Self {
+        Self {
+            line,
+            column,
+            offset,
+        }
     }
 }
 
@@ -104,15 +124,73 @@ impl fmt::Display for Position {
     }
 }
 
+/// A range of source text: `start` is the position of its first character and
+/// `end` the position just past its last.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub const fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    /// Length of the span in bytes.
+    pub const fn len(&self) -> usize {
+        self.end.offset.saturating_sub(self.start.offset)
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.start)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TokenInfo {
     pub value: Token,
     pub position: Position,
+    pub end: Position,
 }
 
 impl TokenInfo {
-    pub const fn new(value: Token, position: Position) -> Self {
-        Self { value, position }
+    /// A token at `position` with nothing after it, so a `\r` ends a line.
+    /// The tokenizer records the end itself, as whether a `\r` ends a line
+    /// depends on the next character.
+    pub fn new(value: Token, position: Position) -> Self {
+        let Position {
+            line,
+            column,
+            offset,
+        } = position;
+        let end = match &value {
+            Token::Whitespace(Whitespace::NewLine | Whitespace::CarriageReturn) => {
+                Position::new(line + 1, 1, offset + 1)
+            }
+            Token::Value(s) => {
+                Position::new(line, column + s.chars().count() as u32, offset + s.len())
+            }
+            token => {
+                let c = match token {
+                    Token::Special(c) => c.as_char(),
+                    Token::Whitespace(c) => c.as_char(),
+                    Token::Value(_) => unreachable!("handled above"),
+                };
+                Position::new(line, column + 1, offset + c.len_utf8())
+            }
+        };
+        Self {
+            value,
+            position,
+            end,
+        }
     }
 
     pub const fn is_special(&self) -> bool {
@@ -126,6 +204,11 @@ impl TokenInfo {
     pub const fn is_whitespace(&self) -> bool {
         matches!(self.value, Token::Whitespace(_))
     }
+
+    /// The position just past the end of this token.
+    pub const fn end(&self) -> Position {
+        self.end
+    }
 }
 
 pub struct Tokenizer<I>
@@ -141,8 +224,8 @@ impl<I: Iterator<Item = char>> Tokenizer<I> {
     pub fn new(iter: I) -> Self {
         Self {
             stream: iter.peekable(),
-            last: Position { line: 1, column: 1 },
-            next: Position { line: 1, column: 1 },
+            last: Position::new(1, 1, 0),
+            next: Position::new(1, 1, 0),
         }
     }
 
@@ -153,10 +236,16 @@ impl<I: Iterator<Item = char>> Tokenizer<I> {
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<char> {
         if let Some(next_char) = self.stream.next() {
-            self.last.line = self.next.line;
-            self.last.column = self.next.column;
-
-            if matches!(next_char, '\n' | '\r') {
+            self.last = self.next;
+            self.next.offset += next_char.len_utf8();
+
+            // A `\r\n` pair is a single line break; the line advances on the `\n`.
+            let newline = match next_char {
+                '\n' => true,
+                '\r' => self.stream.peek() != Some(&'\n'),
+                _ => false,
+            };
+            if newline {
                 self.next.line += 1;
                 self.next.column = 1;
             } else {
@@ -173,27 +262,31 @@ impl<I: Iterator<Item = char>> Tokenizer<I> {
         let mut tokens: Vec<TokenInfo> = Vec::new();
 
         while let Some(c) = self.next() {
-            let token = if let Some(token_type) = Special::from(&c) {
-                TokenInfo::new(Token::Special(token_type), self.last)
-            } else if let Some(token_type) = Whitespace::from(&c) {
-                TokenInfo::new(Token::Whitespace(token_type), self.last)
-            } else {
-                let mut value = String::new();
-                let position = self.last;
-
-                value.push(c);
-                while let Some(c) = self.peek() {
-                    if Special::is_special(c) || c.is_whitespace() {
-                        break;
+            let position = self.last;
+            let value = match (Special::from(&c), Whitespace::from(&c)) {
+                (Some(token_type), _) => Token::Special(token_type),
+                (None, Some(token_type)) => Token::Whitespace(token_type),
+                (None, None) => {
+                    let mut value = String::new();
+
+                    value.push(c);
+                    while let Some(c) = self.peek() {
+                        if Special::is_special(c) || c.is_whitespace() {
+                            break;
+                        }
+                        if let Some(c) = self.next() {
+                            value.push(c);
+                        }
                     }
-                    if let Some(c) = self.next() {
-                        value.push(c);
-                    }
-                }
 
-                TokenInfo::new(Token::Value(value), position)
+                    Token::Value(value)
+                }
             };
-            tokens.push(token);
+            tokens.push(TokenInfo {
+                value,
+                position,
+                end: self.next,
+            });
         }
 
         tokens
@@ -239,33 +332,66 @@ mod tests {
     fn test_simple_entry() {
         let text = "@misc{citekey,\n  author=\"foo\", \ntitle = { bar }\n}";
         let expected = vec![
-            TokenInfo::new(Token::Special(Special::At), Position::new(1, 1)),
-            TokenInfo::new(Token::Value("misc".to_string()), Position::new(1, 2)),
-            TokenInfo::new(Token::Special(Special::BraceLeft), Position::new(1, 6)),
-            TokenInfo::new(Token::Value("citekey".to_string()), Position::new(1, 7)),
-            TokenInfo::new(Token::Special(Special::Comma), Position::new(1, 14)),
-            TokenInfo::new(Token::Whitespace(Whitespace::NewLine), Position::new(1, 15)),
-            TokenInfo::new(Token::Whitespace(Whitespace::Space), Position::new(2, 1)),
-            TokenInfo::new(Token::Whitespace(Whitespace::Space), Position::new(2, 2)),
-            TokenInfo::new(Token::Value("author".to_string()), Position::new(2, 3)),
-            TokenInfo::new(Token::Special(Special::Equals), Position::new(2, 9)),
-            TokenInfo::new(Token::Special(Special::Quote), Position::new(2, 10)),
-            TokenInfo::new(Token::Value("foo".to_string()), Position::new(2, 11)),
-            TokenInfo::new(Token::Special(Special::Quote), Position::new(2, 14)),
-            TokenInfo::new(Token::Special(Special::Comma), Position::new(2, 15)),
-            TokenInfo::new(Token::Whitespace(Whitespace::Space), Position::new(2, 16)),
-            TokenInfo::new(Token::Whitespace(Whitespace::NewLine), Position::new(2, 17)),
-            TokenInfo::new(Token::Value("title".to_string()), Position::new(3, 1)),
-            TokenInfo::new(Token::Whitespace(Whitespace::Space), Position::new(3, 6)),
-            TokenInfo::new(Token::Special(Special::Equals), Position::new(3, 7)),
-            TokenInfo::new(Token::Whitespace(Whitespace::Space), Position::new(3, 8)),
-            TokenInfo::new(Token::Special(Special::BraceLeft), Position::new(3, 9)),
-            TokenInfo::new(Token::Whitespace(Whitespace::Space), Position::new(3, 10)),
-            TokenInfo::new(Token::Value("bar".to_string()), Position::new(3, 11)),
-            TokenInfo::new(Token::Whitespace(Whitespace::Space), Position::new(3, 14)),
-            TokenInfo::new(Token::Special(Special::BraceRight), Position::new(3, 15)),
-            TokenInfo::new(Token::Whitespace(Whitespace::NewLine), Position::new(3, 16)),
-            TokenInfo::new(Token::Special(Special::BraceRight), Position::new(4, 1)),
+            TokenInfo::new(Token::Special(Special::At), Position::new(1, 1, 0)),
+            TokenInfo::new(Token::Value("misc".to_string()), Position::new(1, 2, 1)),
+            TokenInfo::new(Token::Special(Special::BraceLeft), Position::new(1, 6, 5)),
+            TokenInfo::new(Token::Value("citekey".to_string()), Position::new(1, 7, 6)),
+            TokenInfo::new(Token::Special(Special::Comma), Position::new(1, 14, 13)),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::NewLine),
+                Position::new(1, 15, 14),
+            ),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::Space),
+                Position::new(2, 1, 15),
+            ),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::Space),
+                Position::new(2, 2, 16),
+            ),
+            TokenInfo::new(Token::Value("author".to_string()), Position::new(2, 3, 17)),
+            TokenInfo::new(Token::Special(Special::Equals), Position::new(2, 9, 23)),
+            TokenInfo::new(Token::Special(Special::Quote), Position::new(2, 10, 24)),
+            TokenInfo::new(Token::Value("foo".to_string()), Position::new(2, 11, 25)),
+            TokenInfo::new(Token::Special(Special::Quote), Position::new(2, 14, 28)),
+            TokenInfo::new(Token::Special(Special::Comma), Position::new(2, 15, 29)),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::Space),
+                Position::new(2, 16, 30),
+            ),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::NewLine),
+                Position::new(2, 17, 31),
+            ),
+            TokenInfo::new(Token::Value("title".to_string()), Position::new(3, 1, 32)),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::Space),
+                Position::new(3, 6, 37),
+            ),
+            TokenInfo::new(Token::Special(Special::Equals), Position::new(3, 7, 38)),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::Space),
+                Position::new(3, 8, 39),
+            ),
+            TokenInfo::new(Token::Special(Special::BraceLeft), Position::new(3, 9, 40)),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::Space),
+                Position::new(3, 10, 41),
+            ),
+            TokenInfo::new(Token::Value("bar".to_string()), Position::new(3, 11, 42)),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::Space),
+                Position::new(3, 14, 45),
+            ),
+            TokenInfo::new(
+                Token::Special(Special::BraceRight),
+                Position::new(3, 15, 46),
+            ),
+            TokenInfo::new(
+                Token::Whitespace(Whitespace::NewLine),
+                Position::new(3, 16, 47),
+            ),
+            TokenInfo::new(Token::Special(Special::BraceRight), Position::new(4, 1, 48)),
         ];
         let mut tokenizer = Tokenizer::new(text.chars());
         let tokens: Vec<TokenInfo> = tokenizer.tokenize();
@@ -273,6 +399,78 @@ mod tests {
         assert_eq!(tokens, expected);
     }
 
+    #[test]
+    fn test_parens() {
+        let text = "@misc(a)";
+        let expected = vec![
+            Token::Special(Special::At),
+            Token::Value("misc".to_string()),
+            Token::Special(Special::ParenLeft),
+            Token::Value("a".to_string()),
+            Token::Special(Special::ParenRight),
+        ];
+        let mut tokenizer = Tokenizer::new(text.chars());
+        let tokens: Vec<Token> = tokenizer.tokenize().into_iter().map(|t| t.value).collect();
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_whitespace_is_lossless() {
+        let text = "a\r\n\u{a0}b\rc";
+        let mut tokenizer = Tokenizer::new(text.chars());
+        let tokens = tokenizer.tokenize();
+        let positions: Vec<Position> = tokens.iter().map(|t| t.position).collect();
+        assert_eq!(
+            positions,
+            vec![
+                Position::new(1, 1, 0),
+                Position::new(1, 2, 1),
+                Position::new(1, 3, 2),
+                Position::new(2, 1, 3),
+                Position::new(2, 2, 5),
+                Position::new(2, 3, 6),
+                Position::new(3, 1, 7),
+            ]
+        );
+        assert_eq!(
+            stringify(tokens.into_iter().map(|t| t.value).collect()),
+            text
+        );
+    }
+
+    #[test]
+    fn test_token_end_after_carriage_return() {
+        let mut tokenizer = Tokenizer::new("a\r\nb\rc".chars());
+        let ends: Vec<Position> = tokenizer.tokenize().iter().map(TokenInfo::end).collect();
+        assert_eq!(
+            ends,
+            vec![
+                Position::new(1, 2, 1),
+                Position::new(1, 3, 2),
+                Position::new(2, 1, 3),
+                Position::new(2, 2, 4),
+                Position::new(3, 1, 5),
+                Position::new(3, 2, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_token_end() {
+        let mut tokenizer = Tokenizer::new("ab\u{e9} {\nc".chars());
+        let ends: Vec<Position> = tokenizer.tokenize().iter().map(TokenInfo::end).collect();
+        assert_eq!(
+            ends,
+            vec![
+                Position::new(1, 4, 4),
+                Position::new(1, 5, 5),
+                Position::new(1, 6, 6),
+                Position::new(2, 1, 7),
+                Position::new(2, 2, 8),
+            ]
+        );
+    }
+
     #[test]
     fn test_stringify() {
         let tokens = vec![
@@ -7,6 +7,7 @@ use std::process::Command;
 // Cargo sets for integration tests) to exercise --write/--check, exit codes, and
 // in-place file IO, none of which the library-level snippet tests cover.
 
+const EXIT_PARSE_ERROR: i32 = 3;
 const EXIT_REFORMATTED: i32 = 5;
 
 fn bin() -> Command {
@@ -92,3 +93,334 @@ fn write_processes_multiple_files() {
         snippet("sort-entries", "out")
     );
 }
+
+#[test]
+fn check_reports_every_parse_error() {
+    let dir = scratch_dir("parse-errors");
+    let file = dir.join("refs.bib");
+    let input = "@misc{a, title = = {A}}\n\n@misc{b, title = {B}}\n\n@misc{c x}\n";
+    fs::write(&file, input).unwrap();
+
+    let output = bin().arg("--check").arg(&file).output().unwrap();
+
+    assert_eq!(output.status.code(), Some(EXIT_PARSE_ERROR));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let path = file.display();
+    assert!(stderr.contains(&format!("{path}:1:18")), "{stderr}");
+    assert!(stderr.contains(&format!("{path}:5:10")), "{stderr}");
+    // Each error quotes the offending source line.
+    assert!(stderr.contains("5 | @misc{c x}"), "{stderr}");
+    // --check never writes, even around malformed entries.
+    assert_eq!(fs::read_to_string(&file).unwrap(), input);
+}
+
+#[test]
+fn write_formats_around_malformed_entries() {
+    let dir = scratch_dir("malformed-write");
+    let file = dir.join("refs.bib");
+    let broken = "@misc{broken,\n  title = = {B},\n}";
+    let input = format!("@misc{{z,\n  YEAR = 2020}}\n\n{broken}\n\n@misc{{a,\n  YEAR = 2021}}\n");
+    fs::write(&file, &input).unwrap();
+
+    let output = bin().arg("--write").arg(&file).output().unwrap();
+
+    // The file is still formatted, but the run fails on the malformed entry.
+    assert_eq!(output.status.code(), Some(EXIT_PARSE_ERROR));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("kept verbatim"), "{stderr}");
+    let expected =
+        format!("{broken}\n@misc{{a,\n    year = 2021,\n}}\n\n@misc{{z,\n    year = 2020,\n}}\n");
+    assert_eq!(fs::read_to_string(&file).unwrap(), expected);
+}
+
+#[test]
+fn expand_strings_reports_undefined_macro() {
+    let dir = scratch_dir("undefined-macro");
+    let file = dir.join("refs.bib");
+    fs::write(&file, "@misc{a,\n  journal = jmlr,\n}\n").unwrap();
+
+    let output = bin().arg("--expand-strings").arg(&file).output().unwrap();
+
+    assert_eq!(output.status.code(), Some(EXIT_PARSE_ERROR));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Undefined @string macro `jmlr`"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains(&format!("{}:2:13", file.display())),
+        "{stderr}"
+    );
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn crossref_verify_reports_parent_before_child() {
+    let dir = scratch_dir("crossref-verify");
+    let file = dir.join("refs.bib");
+    let input = "@proceedings{conf,\n    year = 2020,\n}\n\n\
+                 @inproceedings{paper,\n    crossref = {conf},\n}\n";
+    fs::write(&file, input).unwrap();
+
+    let output = bin()
+        .args(["--check", "--skip-sort-entries", "--crossref", "verify"])
+        .arg(&file)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(EXIT_PARSE_ERROR));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Entry `conf` is cross-referenced by `paper` but appears before it"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains(&format!("{}:6:16", file.display())),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn crossref_verify_accepts_parent_that_sorting_moves() {
+    let dir = scratch_dir("crossref-verify-sorted");
+    let file = dir.join("refs.bib");
+    let input = "@proceedings{conf,\n    year = 2020,\n}\n\n\
+                 @inproceedings{paper,\n    crossref = {conf},\n}\n";
+    fs::write(&file, input).unwrap();
+
+    // Sorted output already puts the parent after its child.
+    let output = bin()
+        .args(["--crossref", "verify"])
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stderr.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "@inproceedings{paper,\n    crossref = {conf},\n}\n\n\
+         @proceedings{conf,\n    year = 2020,\n}\n"
+    );
+}
+
+#[test]
+fn config_file_is_discovered_and_overridable() {
+    let dir = scratch_dir("config");
+    let nested = dir.join("refs");
+    fs::create_dir_all(&nested).unwrap();
+    let file = nested.join("refs.bib");
+    fs::write(&file, "% note\n@misc{a,\n    year = 2020,\n}\n").unwrap();
+    fs::write(dir.join(".bibtex-format.toml"), "remove-comments = true\n").unwrap();
+
+    // Discovered by walking up from the input.
+    let output = bin().arg(&file).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "@misc{a,\n    year = 2020,\n}\n"
+    );
+
+    // An explicit --config replaces the discovered file.
+    let explicit = dir.join("explicit.toml");
+    fs::write(&explicit, "sort-tags = false\n").unwrap();
+    let output = bin()
+        .arg("--config")
+        .arg(&explicit)
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "% note\n@misc{a,\n    year = 2020,\n}\n"
+    );
+
+    // Invalid settings are rejected with the argument error code.
+    fs::write(&explicit, "sort-tag = false\n").unwrap();
+    let output = bin()
+        .arg("--config")
+        .arg(&explicit)
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unknown field `sort-tag`"), "{stderr}");
+}
+
+#[test]
+fn key_case_reports_lowercased_keys() {
+    let dir = scratch_dir("key-case-report");
+    let file = dir.join("refs.bib");
+    fs::write(&file, "@misc{Smith2020, year = 2020}\n@misc{adams2019}\n").unwrap();
+
+    let output = bin().arg(&file).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("@misc{smith2020,"), "{stdout}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr,
+        format!(
+            "{}: cite key `Smith2020` is written as `smith2020`\n",
+            file.display()
+        )
+    );
+
+    let output = bin()
+        .args(["--key-case", "preserve"])
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("@misc{Smith2020,"));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn key_case_strict_reports_collisions() {
+    let dir = scratch_dir("key-case-strict");
+    let file = dir.join("refs.bib");
+    fs::write(
+        &file,
+        "@misc{Smith2020, year = 2020}\n@misc{smith2020, year = 2021}\n",
+    )
+    .unwrap();
+
+    let output = bin()
+        .args(["--key-case", "strict"])
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(EXIT_PARSE_ERROR));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Cite key `smith2020` differs only in case from `Smith2020`"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains(&format!("{}:2:1", file.display())),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn check_enforces_trailing_comma_convention() {
+    let dir = scratch_dir("trailing-comma");
+    let with_comma = dir.join("with.bib");
+    let without_comma = dir.join("without.bib");
+    fs::write(
+        &with_comma,
+        "@misc{a,\n    title = {A},\n    year = 2020,\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        &without_comma,
+        "@misc{a,\n    title = {A},\n    year = 2020\n}\n",
+    )
+    .unwrap();
+
+    let check = |file: &PathBuf, flags: &[&str]| {
+        bin()
+            .arg("--check")
+            .args(flags)
+            .arg(file)
+            .output()
+            .unwrap()
+            .status
+            .code()
+    };
+    assert_eq!(check(&with_comma, &[]), Some(0));
+    assert_eq!(check(&without_comma, &[]), Some(EXIT_REFORMATTED));
+    assert_eq!(
+        check(&with_comma, &["--no-trailing-comma"]),
+        Some(EXIT_REFORMATTED)
+    );
+    assert_eq!(check(&without_comma, &["--no-trailing-comma"]), Some(0));
+}
+
+#[test]
+fn flags_override_config_both_ways() {
+    let dir = scratch_dir("config-both-ways");
+    let file = dir.join("refs.bib");
+    let input = "% note\n@misc{b, pages = {1-2}}\n@misc{a}\n";
+    fs::write(&file, input).unwrap();
+    let config = dir.join(".bibtex-format.toml");
+    let format = |flags: &[&str]| {
+        let output = bin().args(flags).arg(&file).output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Flags turn off settings the configuration turns on...
+    fs::write(
+        &config,
+        "sort-entries = false\nremove-comments = true\nnormalize-pages = true\n",
+    )
+    .unwrap();
+    assert_eq!(format(&[]), "@misc{b,\n    pages = {1--2},\n}\n@misc{a}\n");
+    assert_eq!(
+        format(&["--sort-entries", "--keep-comments", "--no-normalize-pages"]),
+        "@misc{a}\n\n% note\n@misc{b,\n    pages = {1-2},\n}\n"
+    );
+
+    // ...and on settings it turns off, with the last of two opposing flags
+    // winning.
+    fs::write(
+        &config,
+        "sort-entries = true\nremove-comments = false\nnormalize-pages = false\n",
+    )
+    .unwrap();
+    assert_eq!(
+        format(&[
+            "--skip-sort-entries",
+            "--remove-comments",
+            "--normalize-pages"
+        ]),
+        "@misc{b,\n    pages = {1--2},\n}\n@misc{a}\n"
+    );
+    assert_eq!(
+        format(&[
+            "--no-normalize-pages",
+            "--normalize-pages",
+            "--skip-sort-entries"
+        ]),
+        "% note\n@misc{b,\n    pages = {1--2},\n}\n@misc{a}\n"
+    );
+}
+
+#[test]
+fn expand_and_factor_strings_conflict_across_config_and_flags() {
+    let dir = scratch_dir("expand-factor-conflict");
+    let file = dir.join("refs.bib");
+    fs::write(&file, "@misc{a, year = 2020}\n").unwrap();
+    let config = dir.join(".bibtex-format.toml");
+    let stderr = |flags: &[&str]| {
+        let output = bin().args(flags).arg(&file).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    // Both in the configuration file...
+    fs::write(&config, "expand-strings = true\nfactor-strings = 2\n").unwrap();
+    let message = stderr(&[]);
+    assert!(message.contains("cannot be used together"), "{message}");
+
+    // ...or one from the file and the other from a flag.
+    fs::write(&config, "expand-strings = true\n").unwrap();
+    let message = stderr(&["--factor-strings", "2"]);
+    assert!(message.contains("cannot be used together"), "{message}");
+    fs::write(&config, "factor-strings = 2\n").unwrap();
+    let message = stderr(&["--expand-strings"]);
+    assert!(message.contains("cannot be used together"), "{message}");
+
+    // A flag turning expansion off settles the conflict.
+    fs::write(&config, "expand-strings = true\n").unwrap();
+    let output = bin()
+        .args(["--no-expand-strings", "--factor-strings", "2"])
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
@@ -1,7 +1,10 @@
 #![cfg(test)]
 use test_case::test_case;
 
-use bibtex_format::format::Formatter;
+use bibtex_format::case::Case;
+use bibtex_format::format::{DelimiterStyle, Formatter};
+use bibtex_format::models::{Entries, MonthStyle, NameStyle};
+use bibtex_format::order::{EntrySort, TagOrder};
 use bibtex_format::parse::Parser;
 use bibtex_format::token::Tokenizer;
 use bibtex_format::Result;
@@ -24,6 +27,19 @@ use std::fs;
 //        - validate_snippets_skip_sort: --skip-sort-entries (preserves order
 //          and the original whitespace between elements).
 //        - validate_snippets_remove_comments: --remove-comments.
+//        - validate_snippets_remove_duplicates: --remove-duplicates.
+//        - validate_snippets_expand_strings: --expand-strings.
+//        - validate_snippets_factor_strings: --factor-strings 2.
+//        - validate_snippets_normalize_links: --normalize-links --move-doi-urls.
+//        - validate_snippets_normalize_months: --normalize-months macro.
+//        - validate_snippets_normalize_names: --normalize-names initials.
+//        - validate_snippets_sort_entries_by_author: --sort-entries-by author.
+//        - validate_snippets_tag_order: a custom --tag-order with a per-type
+//          override for inproceedings.
+//        - validate_snippets_casing: --entry-type-case capitalized,
+//          --keyword-case preserve, and --field-name-case upper.
+//        - validate_snippets_quote_delimiters: --delimiters quotes.
+//        - validate_snippets_max_width: --max-width 60.
 //
 // Notes:
 //   - Leading and trailing whitespace is trimmed from the expected output, so
@@ -31,8 +47,10 @@ use std::fs;
 #[test_case("coalesce-multiline-content" ; "coalesce mutliline contents")]
 #[test_case("comment-nested-braces" ; "round-trip @comment with nested braces")]
 #[test_case("comment-travels-with-entry" ; "comment moves with its entry when sorted")]
+#[test_case("crossref-order" ; "keep crossref parents after their children")]
 #[test_case("implicit-comments" ; "attach comments to following entry")]
 #[test_case("non-delimited-content" ; "non-delimited single word contents")]
+#[test_case("paren-delimited-entries" ; "parse entries delimited by parentheses")]
 #[test_case("preserve-title-casing" ; "preserve title casing with braces")]
 #[test_case("quotes-to-braces" ; "convert quotes to braces in tag contents")]
 #[test_case("remove-empty-tags" ; "remove tags with empty content")]
@@ -64,18 +82,129 @@ fn validate_snippets_remove_comments(name: &str) -> Result<()> {
 // entries and keeps entries that share a key but differ. Keep sorted by name.
 #[test_case("remove-duplicates" ; "collapse exact duplicates, keep conflicts")]
 fn validate_snippets_remove_duplicates(name: &str) -> Result<()> {
-    run_snippet_deduped(name, &Formatter::builder().build())
+    run_snippet_with(name, &Formatter::builder().build(), |entries| {
+        entries.remove_duplicates();
+        Ok(())
+    })
 }
 
-fn run_snippet(name: &str, formatter: &Formatter) -> Result<()> {
-    run_snippet_inner(name, formatter, false)
+// Snippets exercising --expand-strings, which substitutes @string and month
+// macros into plain values. Keep sorted by test name.
+#[test_case("expand-strings" ; "expand macros and concatenations")]
+fn validate_snippets_expand_strings(name: &str) -> Result<()> {
+    run_snippet_with(
+        name,
+        &Formatter::builder().build(),
+        Entries::resolve_strings,
+    )
+}
+
+// Snippets exercising --factor-strings 2, which defines @string macros for
+// repeated values. Keep sorted by test name.
+#[test_case("factor-strings" ; "factor repeated values into macros")]
+fn validate_snippets_factor_strings(name: &str) -> Result<()> {
+    run_snippet_with(name, &Formatter::builder().build(), |entries| {
+        entries.factor_strings(2);
+        Ok(())
+    })
+}
+
+// Snippets exercising --normalize-links --move-doi-urls. Keep sorted by test
+// name.
+#[test_case("normalize-links" ; "clean up dois and urls")]
+fn validate_snippets_normalize_links(name: &str) -> Result<()> {
+    run_snippet_with(name, &Formatter::builder().build(), |entries| {
+        entries.normalize_links(true);
+        Ok(())
+    })
+}
+
+// Snippets exercising --normalize-months macro. Keep sorted by test name.
+#[test_case("normalize-months" ; "rewrite months as macros")]
+fn validate_snippets_normalize_months(name: &str) -> Result<()> {
+    run_snippet_with(name, &Formatter::builder().build(), |entries| {
+        entries.normalize_months(MonthStyle::Macro);
+        Ok(())
+    })
+}
+
+// Snippets exercising --normalize-names initials. Keep sorted by test name.
+#[test_case("normalize-names" ; "rewrite names as last first initials")]
+fn validate_snippets_normalize_names(name: &str) -> Result<()> {
+    run_snippet(
+        name,
+        &Formatter::builder()
+            .normalize_names(NameStyle::Initials)
+            .build(),
+    )
+}
+
+// Snippets exercising --sort-entries-by author. Keep sorted by test name.
+#[test_case("sort-entries-by-author" ; "sort by first author then year")]
+fn validate_snippets_sort_entries_by_author(name: &str) -> Result<()> {
+    run_snippet(
+        name,
+        &Formatter::builder().entry_sort(EntrySort::Author).build(),
+    )
+}
+
+// Snippets exercising a custom tag order. Keep sorted by test name.
+#[test_case("tag-order" ; "custom and per-type tag order")]
+fn validate_snippets_tag_order(name: &str) -> Result<()> {
+    let order = TagOrder::new([
+        "author",
+        "title",
+        "booktitle",
+        "journal",
+        "year",
+        "volume",
+        "number",
+        "pages",
+        "doi",
+        "url",
+    ])
+    .with_type("inproceedings", ["booktitle", "author"]);
+    run_snippet(name, &Formatter::builder().tag_order(order).build())
 }
 
-fn run_snippet_deduped(name: &str, formatter: &Formatter) -> Result<()> {
-    run_snippet_inner(name, formatter, true)
+// Snippets exercising casing options. Keep sorted by test name.
+#[test_case("casing" ; "capitalized types, preserved keywords, upper fields")]
+fn validate_snippets_casing(name: &str) -> Result<()> {
+    let formatter = Formatter::builder()
+        .entry_type_case(Case::Capitalized)
+        .keyword_case(Case::Preserve)
+        .field_name_case(Case::Upper)
+        .build();
+    run_snippet(name, &formatter)
 }
 
-fn run_snippet_inner(name: &str, formatter: &Formatter, dedup: bool) -> Result<()> {
+// Snippets exercising --delimiters quotes. Keep sorted by test name.
+#[test_case("quote-delimiters" ; "quote values and protect embedded quotes")]
+fn validate_snippets_quote_delimiters(name: &str) -> Result<()> {
+    run_snippet(
+        name,
+        &Formatter::builder()
+            .delimiters(DelimiterStyle::Quotes)
+            .build(),
+    )
+}
+
+// Snippets exercising --max-width. Keep sorted by test name.
+#[test_case("wrap-long-values" ; "wrap long values at word boundaries")]
+fn validate_snippets_max_width(name: &str) -> Result<()> {
+    run_snippet(name, &Formatter::builder().max_width(60).build())
+}
+
+fn run_snippet(name: &str, formatter: &Formatter) -> Result<()> {
+    run_snippet_with(name, formatter, |_| Ok(()))
+}
+
+/// Run a snippet, applying `transform` to the parsed entries before formatting.
+fn run_snippet_with(
+    name: &str,
+    formatter: &Formatter,
+    transform: impl FnOnce(&mut Entries) -> Result<()>,
+) -> Result<()> {
     let input = format!("tests/snippets/{}.in.bib", name);
     let output = format!("tests/snippets/{}.out.bib", name);
 
@@ -87,9 +216,7 @@ fn run_snippet_inner(name: &str, formatter: &Formatter, dedup: bool) -> Result<(
     let mut parser = Parser::new(tokens.into_iter());
     let mut entries = parser.parse()?;
 
-    if dedup {
-        entries.remove_duplicates();
-    }
+    transform(&mut entries)?;
 
     let formatted = formatter.format_entries(&entries);
 